@@ -36,6 +36,9 @@ pub struct RunAnalysis {
     pub vs_diff: Option<String>,
     pub has_anomaly: bool,
     pub obs_count: usize,
+    /// CPU time summed over the run's cells in every context; None if no
+    /// cell reported it.
+    pub cpu_time_ms: Option<u64>,
 }
 
 /// A group of runs with identical per-context observations.
@@ -649,6 +652,10 @@ pub fn analyze(
         let has_anomaly = output::has_anomalies(majority_obs, None)
             || obs_list.iter().any(|(_, obs)| output::has_anomalies(obs, Some(majority_exit)));
 
+        let cpu_time_ms = obs_list.iter()
+            .filter_map(|(_, o)| o.resources.cpu_time_ms)
+            .reduce(|a, b| a + b);
+
         // Build owned context groups
         let context_groups: Vec<(Vec<String>, Observation)> = groups.iter()
             .map(|(names, obs)| {
@@ -676,6 +683,7 @@ pub fn analyze(
             vs_diff,
             has_anomaly,
             obs_count: obs_list.len(),
+            cpu_time_ms,
         });
     }
 
@@ -47,6 +47,8 @@ impl<'a> ProbeBatch<'a> {
 
         let batch_dir = tempfile::Builder::new()
             .prefix("bgrid_probe_")
+            // Traced cell command lines refer into it
+            .disable_cleanup(self.sandbox.verbose())
             .tempdir()
             .expect("create probe batch dir");
         let out_dir = batch_dir.path().join("out");
@@ -73,10 +75,20 @@ impl<'a> ProbeBatch<'a> {
                 .collect::<Vec<_>>()
                 .join(" ");
 
+            if self.sandbox.verbose() {
+                let host_stdin = if stdin_data.is_some() {
+                    let stdin_file = batch_dir.path().join(format!("s{}", i));
+                    format!("cat {} | ", shell_escape(&stdin_file.to_string_lossy()))
+                } else {
+                    String::new()
+                };
+                self.sandbox.trace_cell(&cell_dir, &HashMap::new(), self.binary, &args_str, &host_stdin);
+            }
+
             script.push_str(&format!(
                 "(cd /batch/c{i} && {stdin}timeout {t} {bin} {args} >/batch/out/{i}.out 2>/batch/out/{i}.err; echo $? >/batch/out/{i}.rc) &\n",
                 i = i, stdin = stdin_part,
-                t = crate::execute::CELL_TIMEOUT_SECS,
+                t = self.sandbox.timeout_arg(),
                 bin = shell_escape(self.binary),
                 args = args_str,
             ));
@@ -101,12 +113,12 @@ impl<'a> ProbeBatch<'a> {
             unsafe { cmd.pre_exec(|| { libc::setpgid(0, 0); Ok(()) }); }
         }
 
-        let timeout_secs = crate::execute::CELL_TIMEOUT_SECS * (self.tasks.len() as u64 + 1);
+        let timeout_ms = self.sandbox.batch_timeout_ms(self.tasks.len());
         match cmd.spawn() {
             Ok(mut child) => {
                 let child_id = child.id();
                 let timer = std::thread::spawn(move || {
-                    std::thread::sleep(std::time::Duration::from_secs(timeout_secs));
+                    std::thread::sleep(std::time::Duration::from_millis(timeout_ms));
                     #[cfg(unix)]
                     unsafe { libc::kill(-(child_id as i32), libc::SIGKILL); }
                 });
@@ -158,12 +170,56 @@ pub struct FlagInfo {
     pub extracted_values: HashMap<String, Vec<String>>, // flag -> values mined from help text
     /// Ordered flag list with resolved metavars (short flags inherit from long aliases).
     pub flags: Vec<(String, Option<String>)>,
+    /// Flags whose description marks them experimental or deprecated.
+    pub stability: HashMap<String, Stability>,
+    /// Documented mutual exclusions ("cannot be used with --x"), both directions.
+    pub conflicts: HashMap<String, Vec<String>>,
+    /// Environment variables the help text mentions, with the value from
+    /// a `NAME=value` example when there is one.
+    pub env_vars: Vec<(String, Option<String>)>,
+    /// Documented exit statuses with their meaning, e.g. (2, "if serious trouble").
+    pub exit_statuses: Vec<(i32, String)>,
+    /// Subcommands the help text points to for more help, e.g. "config"
+    /// from "see 'git help config'", in order of first mention. Needs the
+    /// binary's name, so extract_flag_info leaves it empty and
+    /// generate_initial_script fills it in.
+    pub help_refs: Vec<String>,
+}
+
+/// Stability marker mined from a flag description. Unmarked flags are stable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stability {
+    Experimental,
+    Deprecated,
+}
+
+impl Stability {
+    pub fn label(self) -> &'static str {
+        match self {
+            Stability::Experimental => "experimental",
+            Stability::Deprecated => "deprecated",
+        }
+    }
+}
+
+/// Detect "deprecated"/"obsolete" or "experimental"/"unstable" wording in a
+/// flag description. Deprecation wins when both appear.
+pub fn stability_marker(desc: &str) -> Option<Stability> {
+    let lower = desc.to_lowercase();
+    if lower.contains("deprecated") || lower.contains("obsolete") {
+        Some(Stability::Deprecated)
+    } else if lower.contains("experimental") || lower.contains("unstable") {
+        Some(Stability::Experimental)
+    } else {
+        None
+    }
 }
 
 /// Extract values from a flag description using multiple patterns:
 /// - Single-quoted values: 'auto', 'always', 'never'
 /// - Brace enumerations: {all,none,older}
 /// - Pipe-separated braces: {big|little}
+/// - Pipe-separated parens or angles: (always|never|auto), <bsd|sysv>
 /// - Bracket character sets: [doxn] (individual chars)
 /// - "one of" lists: one of X, Y, or Z
 fn mine_description_values(desc: &str) -> Vec<String> {
@@ -191,6 +247,17 @@ fn mine_description_values(desc: &str) -> Vec<String> {
         }
     }
 
+    // Paren/angle choice lists: --color=<always|never|auto>, (bsd|sysv).
+    // Pipes are required: commas in parens are usually prose, and a lone
+    // <word> is a metavar, not a choice.
+    let choice_re = Regex::new(r"[(<]([a-zA-Z0-9][-a-zA-Z0-9]*(?:\|[a-zA-Z0-9][-a-zA-Z0-9]*)+)[)>]").unwrap();
+    for cap in choice_re.captures_iter(desc) {
+        for item in cap[1].split('|') {
+            let v = item.to_string();
+            if seen.insert(v.clone()) { values.push(v); }
+        }
+    }
+
     // Bracket character sets: [doxn] → individual chars as values
     let bracket_re = Regex::new(r"\[([a-zA-Z]{2,8})\]").unwrap();
     for cap in bracket_re.captures_iter(desc) {
@@ -203,12 +270,118 @@ fn mine_description_values(desc: &str) -> Vec<String> {
     values
 }
 
+/// Flags a description says this flag can't be combined with, e.g.
+/// "cannot be used with --null", "mutually exclusive with -a and -b".
+/// Only flags in the same sentence as the trigger phrase count.
+pub fn mine_conflicts(desc: &str) -> Vec<String> {
+    let trigger_re = Regex::new(
+        r"(?i)(?:(?:cannot|can't|may not|must not) be (?:used|combined|specified)(?: together)? with|mutually exclusive with|incompatible with|conflicts with)"
+    ).unwrap();
+    let flag_re = Regex::new(r"(?:^|[\s,('`])(--[a-zA-Z][-a-zA-Z0-9]*|-[a-zA-Z0-9])\b").unwrap();
+
+    let mut conflicts = Vec::new();
+    for m in trigger_re.find_iter(desc) {
+        let tail = &desc[m.end()..];
+        let sentence = tail.split(". ").next().unwrap_or(tail);
+        for cap in flag_re.captures_iter(sentence) {
+            let flag = cap[1].to_string();
+            if !conflicts.contains(&flag) {
+                conflicts.push(flag);
+            }
+        }
+    }
+    conflicts
+}
+
+/// Environment variables named in help paragraphs that mention the
+/// environment, e.g. "Set LC_ALL=C to get the traditional sort order" or
+/// "the DU_BLOCK_SIZE, BLOCK_SIZE and BLOCKSIZE environment variables".
+/// Names need an underscore or must be a well-known variable, so plain
+/// capitalized words and metavars like SIZE are not taken for variables.
+pub fn mine_env_vars(help_text: &str) -> Vec<(String, Option<String>)> {
+    let var_re = Regex::new(
+        r"\b([A-Z][A-Z0-9]*(?:_[A-Z0-9]+)+|TZ|LANG|TERM|COLUMNS|HOME|PATH|TMPDIR|PAGER|EDITOR|SHELL|BLOCKSIZE)\b(?:=([^\s,;)'`]+))?"
+    ).unwrap();
+
+    let mut vars: Vec<(String, Option<String>)> = Vec::new();
+    for paragraph in help_text.split("\n\n") {
+        if !paragraph.to_lowercase().contains("environment") {
+            continue;
+        }
+        for cap in var_re.captures_iter(paragraph) {
+            let name = cap[1].to_string();
+            let value = cap.get(2).map(|v| v.as_str().trim_end_matches('.').to_string());
+            match vars.iter_mut().find(|(n, _)| *n == name) {
+                Some((_, existing)) => { if existing.is_none() { *existing = value; } }
+                None => vars.push((name, value)),
+            }
+        }
+    }
+    vars
+}
+
+/// Exit statuses documented in help paragraphs that mention them, both
+/// the coreutils list form (" 1  if minor problems,") and prose such as
+/// "Exit status is 0 if any line is selected, 1 otherwise".
+pub fn mine_exit_statuses(help_text: &str) -> Vec<(i32, String)> {
+    let code_re = Regex::new(r"(?:^|[\s,;])(\d{1,3})\s+((?:if|when|on|otherwise)\b[^,.;(\n]*)").unwrap();
+    let stated_re = Regex::new(r"(?i)exit (?:status|code) is (\d{1,3})\b").unwrap();
+
+    let mut statuses: Vec<(i32, String)> = Vec::new();
+    for paragraph in help_text.split("\n\n") {
+        let lower = paragraph.to_lowercase();
+        if !lower.contains("exit status") && !lower.contains("exit code") {
+            continue;
+        }
+        let found = code_re.captures_iter(paragraph)
+            .map(|cap| (cap[1].to_string(), cap[2].split_whitespace().collect::<Vec<_>>().join(" ")))
+            .chain(stated_re.captures_iter(paragraph).map(|cap| (cap[1].to_string(), String::new())));
+        for (code, meaning) in found {
+            let Ok(code) = code.parse::<i32>() else { continue };
+            if code <= 255 && !statuses.iter().any(|(c, _)| *c == code) {
+                statuses.push((code, meaning));
+            }
+        }
+    }
+    statuses.sort_by_key(|(c, _)| *c);
+    statuses
+}
+
+/// Subcommands named in help cross-references: "see 'git help config'",
+/// "Run 'cargo build --help' for more", or any quoted `tool help X`. Only
+/// references to `binary` (by basename) count, so prose like "see the help
+/// for ..." isn't taken for one. Placeholders (COMMAND, <command>) and the
+/// tool's own name are not subcommands.
+pub fn mine_help_refs(help_text: &str, binary: &str) -> Vec<String> {
+    let name = std::path::Path::new(binary).file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| binary.to_string());
+    let ref_re = Regex::new(
+        r#"(?:(?i:\b(?:see|run|try|use|type))\s+['"`‘]?|['"`‘])([a-z][-a-z0-9_.]*)\s+(?:help\s+([a-z][-a-z0-9]*)|([a-z][-a-z0-9]*)\s+--help)\b"#
+    ).unwrap();
+
+    let mut refs: Vec<String> = Vec::new();
+    for cap in ref_re.captures_iter(help_text) {
+        let tool = &cap[1];
+        if tool != name {
+            continue;
+        }
+        let Some(sub) = cap.get(2).or(cap.get(3)).map(|m| m.as_str()) else { continue };
+        if sub != tool && sub != "help" && !refs.iter().any(|r| r == sub) {
+            refs.push(sub.to_string());
+        }
+    }
+    refs
+}
+
 /// Extract flag descriptions and aliases from --help text.
 pub fn extract_flag_info(help_text: &str) -> FlagInfo {
     let mut descs: HashMap<String, String> = HashMap::new();
     let mut aliases: HashMap<String, String> = HashMap::new();
     let mut all_flags: HashSet<String> = HashSet::new();
     let mut extracted_values: HashMap<String, Vec<String>> = HashMap::new();
+    let mut stability: HashMap<String, Stability> = HashMap::new();
+    let mut conflicts: HashMap<String, Vec<String>> = HashMap::new();
 
     // Flag regexes for the unified pass
     let flag_re = Regex::new(
@@ -289,8 +462,19 @@ pub fn extract_flag_info(help_text: &str) -> FlagInfo {
             }
 
             // Update descriptions with full multi-line text
+            let marker = stability_marker(&desc);
+            let excludes = mine_conflicts(&desc);
             for name in &names {
                 descs.insert(name.clone(), desc.clone());
+                if let Some(m) = marker {
+                    stability.insert(name.clone(), m);
+                }
+                for other in excludes.iter().filter(|o| !names.contains(o)) {
+                    for (a, b) in [(name, other), (other, name)] {
+                        let list = conflicts.entry(a.clone()).or_default();
+                        if !list.contains(b) { list.push(b.clone()); }
+                    }
+                }
             }
 
             // Record alias pairs
@@ -325,39 +509,115 @@ pub fn extract_flag_info(help_text: &str) -> FlagInfo {
     }
 
     all_flags = seen_flags;
-    FlagInfo { descs, aliases, all_flags, extracted_values, flags }
+    let env_vars = mine_env_vars(help_text);
+    let exit_statuses = mine_exit_statuses(help_text);
+    FlagInfo {
+        descs, aliases, all_flags, extracted_values, flags, stability, conflicts, env_vars, exit_statuses,
+        help_refs: Vec::new(),
+    }
+}
+
+/// Help sources tried in order: flags first, then a `help` subcommand, then
+/// a bare invocation (many tools print usage when given no arguments).
+pub const HELP_SOURCES: &[&[&str]] = &[&["--help"], &["-h"], &["help"], &[]];
+
+/// Cap on captured help bytes; a bare invocation of e.g. `yes` never stops.
+const MAX_HELP_BYTES: usize = 256 * 1024;
+
+/// Where try_help's script saves the exit status of the help invocation.
+const HELP_RC_FILE: &str = ".bgrid_help_rc";
+
+/// What one help source produced.
+#[derive(Debug, PartialEq)]
+enum HelpOutput {
+    Text(String),
+    Nothing,
+    /// timeout(1) couldn't run the binary (126/127), so no source will work.
+    NotRunnable(String),
 }
 
-/// Try --help, then -h to get help text from a binary.
-pub fn try_help(binary: &str, sub_args: &[&str], sandbox: &Sandbox) -> Result<String> {
+/// Classify one help attempt by its output and the binary's exit status.
+/// `help` and a bare invocation often just print a usage error (`rm: missing
+/// operand`), so for non-flag sources the output must also come from a
+/// successful run or carry a `Usage:`/`Options:` header.
+fn classify_help_output(stdout: &str, stderr: &str, rc: Option<i32>, flag_source: bool) -> HelpOutput {
+    if matches!(rc, Some(126) | Some(127)) {
+        return HelpOutput::NotRunnable(stderr.trim().to_string());
+    }
+    // Skip bwrap's own errors (bad bind, no user namespaces, etc.)
+    if stderr.starts_with("bwrap:") && stdout.is_empty() {
+        return HelpOutput::Nothing;
+    }
+    let text = if stdout.len() > stderr.len() { stdout } else { stderr };
+    let has_header = text.lines().any(|l| {
+        let l = l.trim_start().to_ascii_lowercase();
+        l.starts_with("usage:") || l.starts_with("options:")
+    });
+    if text.contains('-') && text.len() > 20 && (flag_source || rc == Some(0) || has_header) {
+        HelpOutput::Text(text.to_string())
+    } else {
+        HelpOutput::Nothing
+    }
+}
+
+/// Get help text from a binary, trying each of HELP_SOURCES in order (or only
+/// `help_flag` when given) and keeping the first non-trivial output.
+pub fn try_help(
+    binary: &str,
+    sub_args: &[&str],
+    sandbox: &Sandbox,
+    help_flag: Option<&str>,
+) -> Result<String> {
     let tmp = tempfile::Builder::new().prefix("bgrid_help_").tempdir()
         .context("create help sandbox")?;
 
-    for help_flag in &["--help", "-h"] {
-        let mut args: Vec<&str> = sub_args.to_vec();
-        args.push(help_flag);
+    let forced = help_flag.map(|f| [f]);
+    let sources: Vec<&[&str]> = match &forced {
+        Some(flag) => vec![flag.as_slice()],
+        None => HELP_SOURCES.to_vec(),
+    };
+    // Bounded in time and size, since non-flag sources may not exit on their
+    // own. The pipe hides timeout's status, so it is saved to a file.
+    let script = format!("{{ timeout {} \"$@\"; echo $? >{}; }} | head -c {}",
+        sandbox.timeout_arg(), HELP_RC_FILE, MAX_HELP_BYTES);
+    let rc_path = tmp.path().join(HELP_RC_FILE);
+
+    for source in &sources {
+        let mut args: Vec<&str> = vec!["-c", &script, "sh", binary];
+        args.extend_from_slice(sub_args);
+        args.extend_from_slice(source);
         let env = HashMap::new();
-        let mut cmd = sandbox.command(binary, &args, tmp.path(), &env);
+        let mut cmd = sandbox.command("sh", &args, tmp.path(), &env);
         cmd.stdin(std::process::Stdio::null())
             .stdout(std::process::Stdio::piped())
             .stderr(std::process::Stdio::piped());
 
+        let _ = std::fs::remove_file(&rc_path);
         let output = cmd.output();
 
         if let Ok(out) = output {
             let stdout = String::from_utf8_lossy(&out.stdout);
             let stderr = String::from_utf8_lossy(&out.stderr);
-            // Skip bwrap's own errors (binary not found, etc.)
-            if stderr.starts_with("bwrap:") && stdout.is_empty() {
-                continue;
-            }
-            let text = if stdout.len() > stderr.len() { stdout } else { stderr };
-            if text.contains('-') && text.len() > 20 {
-                return Ok(text.to_string());
+            let rc = std::fs::read_to_string(&rc_path).ok().and_then(|s| s.trim().parse().ok());
+            let flag_source = source.first().is_some_and(|a| a.starts_with('-'));
+            match classify_help_output(&stdout, &stderr, rc, flag_source) {
+                HelpOutput::Text(text) => {
+                    let label = if source.is_empty() { "(no args)".to_string() } else { source.join(" ") };
+                    eprintln!("  help: from {}", label);
+                    return Ok(text);
+                }
+                HelpOutput::NotRunnable(why) => {
+                    anyhow::bail!("{} cannot be run in the sandbox: {}", binary, why);
+                }
+                HelpOutput::Nothing => {}
             }
         }
     }
-    anyhow::bail!("could not get help text from {} (tried --help and -h)", binary)
+    let tried = sources.iter()
+        .map(|s| if s.is_empty() { "no args".to_string() } else { s.join(" ") })
+        .collect::<Vec<_>>()
+        .join(", ");
+    anyhow::bail!("could not get help text from {} (tried {})", binary, tried)
 }
 
 
@@ -922,11 +1182,13 @@ pub fn generate_initial_script(
     binary: &str,
     sub_args: &[&str],
     sandbox: &Sandbox,
+    help_flag: Option<&str>,
 ) -> Result<(Script, FlagInfo)> {
     // --- Factor identification ---
     let t0 = std::time::Instant::now();
-    let help_text = try_help(binary, sub_args, sandbox)?;
-    let flag_info = extract_flag_info(&help_text);
+    let help_text = try_help(binary, sub_args, sandbox, help_flag)?;
+    let mut flag_info = extract_flag_info(&help_text);
+    flag_info.help_refs = mine_help_refs(&help_text, binary);
     let mut flags = flag_info.flags.clone();
     let t_parse = t0.elapsed();
 
@@ -962,6 +1224,9 @@ pub fn generate_initial_script(
     };
 
     let t_probe = t0.elapsed();
+    // Fixed iteration order so the same binary yields the same run list.
+    let mut extra_solo_sorted: Vec<(&String, &Vec<String>)> = extra_solo_values.iter().collect();
+    extra_solo_sorted.sort();
 
     // --- Design construction ---
     let contexts = crate::data::build_contexts();
@@ -986,7 +1251,7 @@ pub fn generate_initial_script(
         let base_args: Vec<Arg> = sub_prefix.iter().cloned()
             .chain(pattern.iter().map(&to_arg))
             .collect();
-        runs.push(Run { args: base_args.clone(), in_contexts: None, diff_from: None });
+        runs.push(Run { args: base_args.clone(), in_contexts: None, diff_from: None, stdin: None });
         for (flag, metavar) in flags.iter() {
             let mut args = sub_prefix.clone();
             // Include prerequisite companion if this flag needs one.
@@ -999,7 +1264,7 @@ pub fn generate_initial_script(
                 push_flag_arg_at(&mut prereq_base, insert_pos, cf, Some(cv));
                 // Ensure the prerequisite base run exists
                 if !runs.iter().any(|r| r.args == prereq_base) {
-                    runs.push(Run { args: prereq_base.clone(), in_contexts: None, diff_from: None });
+                    runs.push(Run { args: prereq_base.clone(), in_contexts: None, diff_from: None, stdin: None });
                 }
                 prereq_base
             } else {
@@ -1007,26 +1272,26 @@ pub fn generate_initial_script(
             };
             push_flag_arg(&mut args, flag, metavar.as_deref());
             args.extend(pattern.iter().map(&to_arg));
-            runs.push(Run { args, in_contexts: None, diff_from: Some(diff_base) });
+            runs.push(Run { args, in_contexts: None, diff_from: Some(diff_base), stdin: None });
         }
         // Extra solo runs for additional working values
-        for (flag, extra_vals) in &extra_solo_values {
+        for &(flag, extra_vals) in &extra_solo_sorted {
             for val in extra_vals {
                 let mut args = sub_prefix.clone();
                 push_flag_arg(&mut args, flag, Some(val));
                 args.extend(pattern.iter().map(&to_arg));
-                runs.push(Run { args, in_contexts: None, diff_from: Some(base_args.clone()) });
+                runs.push(Run { args, in_contexts: None, diff_from: Some(base_args.clone()), stdin: None });
             }
         }
     }
     // Bare-args run (no positional args) — for stdin contexts
     if stdin_works {
         let bare_args: Vec<Arg> = sub_prefix.clone();
-        runs.push(Run { args: bare_args.clone(), in_contexts: None, diff_from: None });
+        runs.push(Run { args: bare_args.clone(), in_contexts: None, diff_from: None, stdin: None });
         for (flag, metavar) in flags.iter() {
             let mut args = sub_prefix.clone();
             push_flag_arg(&mut args, flag, metavar.as_deref());
-            runs.push(Run { args, in_contexts: None, diff_from: Some(bare_args.clone()) });
+            runs.push(Run { args, in_contexts: None, diff_from: Some(bare_args.clone()), stdin: None });
         }
     }
 
@@ -1045,6 +1310,7 @@ pub fn generate_initial_script(
                 args.extend(first_pattern.iter().map(&to_arg));
                 runs.push(Run {
                     args, in_contexts: None,                    diff_from: Some(base_args.clone()),
+                    stdin: None,
                 });
             }
             for (flag, _) in zero_flags.iter().take(3) {
@@ -1053,12 +1319,14 @@ pub fn generate_initial_script(
                 args1.extend(first_pattern.iter().map(&to_arg));
                 runs.push(Run {
                     args: args1, in_contexts: None,                    diff_from: Some(base_args.clone()),
+                    stdin: None,
                 });
                 let mut args2 = sub_prefix.clone();
                 push_flag_arg(&mut args2, flag, Some("2147483647"));
                 args2.extend(first_pattern.iter().map(&to_arg));
                 runs.push(Run {
                     args: args2, in_contexts: None,                    diff_from: Some(base_args.clone()),
+                    stdin: None,
                 });
             }
         }
@@ -1086,6 +1354,7 @@ pub fn generate_initial_script(
             args: base_args.clone(),
             in_contexts: Some(combo_contexts.clone()),
             diff_from: None,
+            stdin: None,
         });
 
         let mut all_flag_args: Vec<Vec<Arg>> = Vec::new();
@@ -1113,6 +1382,7 @@ pub fn generate_initial_script(
                     args,
                     in_contexts: Some(combo_contexts.clone()),
                     diff_from: Some(base_args.clone()),
+                    stdin: None,
                 });
             }
         }
@@ -1122,9 +1392,12 @@ pub fn generate_initial_script(
     {
         let mut err_args = sub_prefix.clone();
         err_args.push(Arg::Literal("nonexistent-file.txt".into()));
-        runs.push(Run { args: err_args, in_contexts: None, diff_from: None });
+        runs.push(Run { args: err_args, in_contexts: None, diff_from: None, stdin: None });
     }
 
+    // Mined values and combos are as untrusted as a hand-written probe
+    crate::parse::validate_runs(&runs)?;
+
     let t_total = t0.elapsed();
     eprintln!("  discovery: parse={}ms patterns={}ms probe={}ms design={}ms total={}ms",
         t_parse.as_millis(), (t_patterns - t_parse).as_millis(),
@@ -1134,3 +1407,321 @@ pub fn generate_initial_script(
     Ok((Script { contexts, runs }, flag_info))
 }
 
+/// Discover flags and the invocation pattern, then return a probe-file
+/// skeleton for manual authoring (`bgrid --skeleton`). No value probing
+/// or grid design: the skeleton is a starting point, not the explore design.
+pub fn generate_skeleton(
+    binary: &str,
+    sub_args: &[&str],
+    sandbox: &Sandbox,
+    help_flag: Option<&str>,
+) -> Result<String> {
+    let help_text = try_help(binary, sub_args, sandbox, help_flag)?;
+    let flag_info = extract_flag_info(&help_text);
+    let (working_patterns, stdin_works, _) = probe_arg_patterns(binary, sub_args, sandbox, &help_text);
+    let pattern = working_patterns.into_iter().next().unwrap_or_default();
+
+    let label = std::iter::once(binary).chain(sub_args.iter().copied()).collect::<Vec<_>>().join(" ");
+    Ok(format_skeleton(&label, &flag_info, sub_args, &pattern, stdin_works))
+}
+
+/// Render a skeleton: one context matching the probe workspace, a base
+/// run, and one diffed run per flag (long form preferred) annotated with
+/// its help description. Values not documented in help are marked guessed.
+/// Commented-out templates follow for stdin (if the binary reads it) and
+/// for varying the environment variables the help text mentions.
+fn format_skeleton(
+    label: &str,
+    flag_info: &FlagInfo,
+    sub_args: &[&str],
+    pattern: &[String],
+    stdin_works: bool,
+) -> String {
+    use crate::parse::quote;
+    use std::fmt::Write;
+
+    let join = |args: &[String]| args.iter().map(|a| quote(a)).collect::<Vec<_>>().join(" ");
+    let base: Vec<String> = sub_args.iter().map(|s| s.to_string()).chain(pattern.iter().cloned()).collect();
+
+    let mut out = String::new();
+    let _ = writeln!(out, "# bgrid skeleton for {} ({} flags from help)", label, flag_info.flags.len());
+    let _ = writeln!(out, "# Add contexts and vary blocks, drop runs you don't need, then:");
+    let _ = writeln!(out, "#   bgrid {} this.probe", label);
+    let _ = writeln!(out);
+    let _ = writeln!(out, "context \"base\"");
+    let _ = writeln!(out, "  file \"input.txt\" \"cherry\" \"apple\" \"banana\"");
+    let _ = writeln!(out, "  file \"other.txt\" \"hello world\"");
+    let _ = writeln!(out, "  dir \"subdir\"");
+    let _ = writeln!(out, "  file \"subdir/nested.txt\" \"nested\"");
+    let _ = writeln!(out);
+    let _ = writeln!(out, "run {}", join(&base));
+    let _ = writeln!(out);
+    let _ = writeln!(out, "from {}", join(&base));
+
+    for (flag, metavar) in &flag_info.flags {
+        // One run per flag: skip a short flag whose long alias is listed.
+        if !flag.starts_with("--") {
+            if let Some(long) = flag_info.aliases.get(flag) {
+                if flag_info.flags.iter().any(|(f, _)| f == long) {
+                    continue;
+                }
+            }
+        }
+
+        // Prefer a value documented in help; otherwise the first metavar candidate.
+        let documented = flag_info.extracted_values.get(flag).and_then(|vals| vals.first().cloned());
+        let guessed = documented.is_none() && metavar.is_some();
+        let value = documented.or_else(|| {
+            metavar.as_deref().and_then(|mv| candidates(mv).first().map(|c| c.to_string()))
+        });
+        let mut line: Vec<String> = sub_args.iter().map(|s| s.to_string()).collect();
+        match &value {
+            Some(v) if flag.starts_with("--") => line.push(format!("{}={}", flag, v)),
+            Some(v) => line.extend([flag.clone(), v.clone()]),
+            None => line.push(flag.clone()),
+        }
+        line.extend(pattern.iter().cloned());
+
+        let mut notes: Vec<String> = Vec::new();
+        if let Some(desc) = flag_info.descs.get(flag)
+            .or_else(|| flag_info.aliases.get(flag).and_then(|a| flag_info.descs.get(a)))
+        {
+            notes.push(desc.split_whitespace().collect::<Vec<_>>().join(" "));
+        }
+        if let (true, Some(mv)) = (guessed, metavar) {
+            notes.push(format!("[{} guessed]", mv));
+        }
+        if notes.is_empty() {
+            let _ = writeln!(out, "  run {}", join(&line));
+        } else {
+            let _ = writeln!(out, "  run {}  # {}", join(&line), notes.join(" "));
+        }
+    }
+
+
+    if stdin_works {
+        let piped: Vec<String> = sub_args.iter().map(|s| s.to_string()).chain(["-".to_string()]).collect();
+        let _ = writeln!(out);
+        let _ = writeln!(out, "# The binary reads stdin; uncomment to observe it (\"-\" names stdin):");
+        let _ = writeln!(out, "# run {}", join(&piped));
+        let _ = writeln!(out, "#   stdin \"cherry\" \"apple\" \"banana\"");
+    }
+
+    if !flag_info.env_vars.is_empty() {
+        let _ = writeln!(out);
+        let _ = writeln!(out, "# Help mentions these environment variables; uncomment to vary them:");
+        let _ = writeln!(out, "# vary from \"base\"");
+        for (var, value) in &flag_info.env_vars {
+            match value {
+                Some(v) => { let _ = writeln!(out, "#   env {} {}", var, quote(v)); }
+                None => { let _ = writeln!(out, "#   env {} \"\"  # no example value in help", var); }
+            }
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stability_markers() {
+        let help = "\
+Options:
+  -a, --all             do not ignore entries starting with .
+      --old-style       use the old format (deprecated)
+      --turbo           EXPERIMENTAL: use the new engine
+      --fast            use the new engine; unstable, may
+                          change between releases
+";
+        let fi = extract_flag_info(help);
+        assert_eq!(fi.stability.get("--old-style"), Some(&Stability::Deprecated));
+        assert_eq!(fi.stability.get("--turbo"), Some(&Stability::Experimental));
+        assert_eq!(fi.stability.get("--fast"), Some(&Stability::Experimental));
+        assert!(!fi.stability.contains_key("--all"));
+        assert!(!fi.stability.contains_key("-a"));
+    }
+
+    #[test]
+    fn test_mine_choice_lists() {
+        assert_eq!(mine_description_values("--color=<always|never|auto>"),
+            vec!["always", "never", "auto"]);
+        assert_eq!(mine_description_values("checksum style (bsd|sysv), default bsd"),
+            vec!["bsd", "sysv"]);
+        assert_eq!(mine_description_values("--format={json|text}"), vec!["json", "text"]);
+        // Metavars and prose parentheticals are not choice lists
+        assert!(mine_description_values("read names from <file> (one per line)").is_empty());
+        assert!(mine_description_values("sort by size (largest, first)").is_empty());
+    }
+
+    #[test]
+    fn test_mine_conflicts() {
+        assert_eq!(mine_conflicts("end lines with NUL; cannot be used with --lines or -c."),
+            vec!["--lines", "-c"]);
+        assert_eq!(mine_conflicts("Mutually exclusive with '--sort'. See also --time."),
+            vec!["--sort"]);
+        assert!(mine_conflicts("use with --color to enable").is_empty());
+
+        let help = "\
+Options:
+  -z, --zero            end output with NUL; incompatible with --json
+      --json            print JSON
+";
+        let fi = extract_flag_info(help);
+        assert_eq!(fi.conflicts.get("-z"), Some(&vec!["--json".to_string()]));
+        assert_eq!(fi.conflicts.get("--zero"), Some(&vec!["--json".to_string()]));
+        assert!(fi.conflicts.get("--json").is_some_and(|c| c.contains(&"--zero".to_string())));
+    }
+
+    #[test]
+    fn test_skeleton_parses() {
+        let help = "\
+Options:
+  -r, --reverse         reverse the result of comparisons
+      --sort=WORD       sort by WORD: 'general-numeric', 'month'
+  -k KEYDEF             sort via a key; KEYDEF gives \"location\"
+";
+        let fi = extract_flag_info(help);
+        let pattern = vec!["input.txt".to_string()];
+        let text = format_skeleton("sort", &fi, &[], &pattern, false);
+        assert!(text.contains("run \"--sort=general-numeric\" \"input.txt\""), "{}", text);
+        assert!(!text.contains("run \"-r\""), "short alias should be folded: {}", text);
+
+        let script = crate::parse::parse_script(&text).unwrap();
+        assert_eq!(script.contexts.len(), 1);
+        assert_eq!(script.runs.len(), 4);
+        let keyed = script.runs.iter().find(|r| r.args.first() == Some(&Arg::from("-k"))).unwrap();
+        assert_eq!(keyed.args, vec![Arg::from("-k"), Arg::from("1"), Arg::from("input.txt")]);
+        assert_eq!(keyed.diff_from, Some(vec![Arg::from("input.txt")]));
+    }
+
+    #[test]
+    fn test_mine_env_vars() {
+        let help = "\
+Options:
+  -r, --reverse         reverse the result of comparisons
+  -B, --block-size=SIZE  scale sizes by SIZE
+
+*** WARNING ***
+The locale specified by the environment affects sort order.
+Set LC_ALL=C to get the traditional sort order.
+
+Display values are in units of the first available SIZE from --block-size,
+and the DU_BLOCK_SIZE, BLOCK_SIZE and BLOCKSIZE environment variables.
+
+FIELD_LIST is a comma-separated list of fields.
+";
+        let vars = mine_env_vars(help);
+        let names: Vec<&str> = vars.iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(names, vec!["LC_ALL", "DU_BLOCK_SIZE", "BLOCK_SIZE", "BLOCKSIZE"]);
+        assert_eq!(vars[0].1.as_deref(), Some("C"));
+        assert_eq!(vars[1].1, None);
+    }
+
+    #[test]
+    fn test_mine_exit_statuses() {
+        let coreutils = "\
+Options:
+  -a, --all             do not ignore entries starting with .
+
+Exit status:
+ 0  if OK,
+ 1  if minor problems (e.g., cannot access subdirectory),
+ 2  if serious trouble (e.g., cannot access command-line argument).
+";
+        assert_eq!(mine_exit_statuses(coreutils), vec![
+            (0, "if OK".to_string()),
+            (1, "if minor problems".to_string()),
+            (2, "if serious trouble".to_string()),
+        ]);
+
+        let grep = "\
+Exit status is 0 if any line is selected, 1 otherwise;
+if any error occurs and -q is not given, the exit status is 2.
+";
+        assert_eq!(mine_exit_statuses(grep), vec![
+            (0, "if any line is selected".to_string()),
+            (1, "otherwise".to_string()),
+            (2, String::new()),
+        ]);
+
+        assert!(mine_exit_statuses("  -n NUM   print NUM lines if set\n").is_empty());
+    }
+
+    #[test]
+    fn test_missing_binary_is_not_help() {
+        let missing = "timeout: failed to run command 'foo-bar': No such file or directory\n";
+        assert_eq!(classify_help_output("", missing, Some(127), true),
+            HelpOutput::NotRunnable(missing.trim().to_string()));
+        let denied = "timeout: failed to run command './tool': Permission denied\n";
+        assert!(matches!(classify_help_output("", denied, Some(126), true), HelpOutput::NotRunnable(_)));
+
+        assert_eq!(classify_help_output("", "bwrap: Can't find source path /nix: No such file\n", None, true),
+            HelpOutput::Nothing);
+        let help = "Usage: sort [OPTION]... [FILE]...\n  -r, --reverse  reverse\n";
+        assert_eq!(classify_help_output(help, "", Some(0), true), HelpOutput::Text(help.to_string()));
+        // A bare invocation that prints usage and fails is still help
+        assert!(matches!(classify_help_output("", help, Some(2), false), HelpOutput::Text(_)));
+    }
+
+    #[test]
+    fn test_usage_error_is_not_help() {
+        let usage_error = "rm: missing operand\nTry 'rm --help' for more information.\n";
+        assert_eq!(classify_help_output("", usage_error, Some(1), false), HelpOutput::Nothing);
+        // A failing flag source is still taken (e.g. `-h` exiting 1 with usage)
+        assert!(matches!(classify_help_output("", usage_error, Some(1), true), HelpOutput::Text(_)));
+        let listing = "Commands:\n  add     add a file\n  rm      remove a file (see rm -h)\n";
+        assert!(matches!(classify_help_output(listing, "", Some(0), false), HelpOutput::Text(_)));
+    }
+
+    #[test]
+    fn test_mine_help_refs() {
+        let git = "\
+See 'git help <command>' or 'git help <concept>'
+to read about a specific subcommand or concept.
+See 'git help git' for an overview of the system.
+For remotes, see 'git help remote'; see also `git help config`.
+";
+        assert_eq!(mine_help_refs(git, "git"), vec!["remote", "config"]);
+        assert_eq!(mine_help_refs(git, "/usr/bin/git"), vec!["remote", "config"]);
+
+        let cargo = "Run 'cargo COMMAND --help' or 'cargo build --help' for details.\n";
+        assert_eq!(mine_help_refs(cargo, "cargo"), vec!["build"]);
+
+        assert!(mine_help_refs("Try 'tar --help' for more information.\n", "tar").is_empty());
+
+        // Prose that only looks like a reference, or names another tool
+        let prose = "\
+For details, see the help for each option.
+Use this help text as a guide.
+See 'gitk help view' for the viewer.
+";
+        assert!(mine_help_refs(prose, "git").is_empty());
+    }
+
+    #[test]
+    fn test_skeleton_templates_uncomment() {
+        let help = "\
+Options:
+  -r, --reverse         reverse the result of comparisons
+
+The locale specified by the environment affects sort order.
+Set LC_ALL=C to get the traditional sort order.
+";
+        let fi = extract_flag_info(help);
+        let pattern = vec!["input.txt".to_string()];
+        let text = format_skeleton("sort", &fi, &[], &pattern, true);
+        assert!(text.contains("#   env LC_ALL \"C\""), "{}", text);
+
+        // Uncommenting the templates must yield a valid probe file
+        let uncommented: String = text.lines()
+            .map(|l| l.strip_prefix("# ").filter(|r| ["run ", "vary ", "  stdin ", "  env "].iter().any(|p| r.starts_with(p))).unwrap_or(l))
+            .collect::<Vec<_>>().join("\n");
+        let script = crate::parse::parse_script(&uncommented).unwrap();
+        assert!(script.contexts.iter().any(|c| c.name.contains("LC_ALL")));
+        let piped = script.runs.iter().find(|r| r.args == vec![Arg::from("-")]).unwrap();
+        assert!(piped.stdin.is_some());
+    }
+}
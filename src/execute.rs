@@ -1,23 +1,30 @@
 //! Execute the grid: states × invocations → observations.
 
-use crate::parse::{Script, Run};
+use crate::parse::{Script, Run, StdinSource};
 use crate::sandbox::{self, Sandbox};
 use anyhow::Result;
 use std::collections::{HashMap, HashSet};
 use std::path::Path;
 use std::process::Stdio;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 
 /// Resource usage from a single execution.
 #[derive(Debug, Clone, Default)]
 pub struct ResourceUsage {
     pub wall_time_ms: u64,
+    /// User + system CPU time of the cell's processes, from the shell's
+    /// `times` builtin. None if the cell was killed before reporting.
+    pub cpu_time_ms: Option<u64>,
 }
 
 /// Observation from a single execution.
 #[derive(Debug, Clone)]
 pub struct Observation {
     pub stdout: String,
+    /// Byte length of stdout when it was not valid UTF-8. `stdout` then
+    /// holds the text with invalid bytes escaped as `\xNN`, so distinct
+    /// binary outputs still compare as distinct.
+    pub binary_stdout: Option<usize>,
     pub stderr: String,
     pub exit_code: Option<i32>,
     pub fs_changes: Vec<FsChange>,
@@ -37,6 +44,10 @@ pub struct GridResult {
     pub cells: HashMap<(String, usize), Observation>,
     pub setup_failures: HashMap<String, String>,
     pub context_count: usize,
+    /// Peak resident set size in KiB of the largest process in any context
+    /// batch. Cells share their context's bwrap, so this is not per cell.
+    /// None if the kernel reported nothing.
+    pub peak_rss_kb: Option<u64>,
 }
 
 /// Snapshot entry: size, mode, and content hash for change detection.
@@ -159,7 +170,8 @@ fn diff_snapshots(before: &FsSnapshot, after: &FsSnapshot) -> Vec<FsChange> {
     changes
 }
 
-/// Per-cell timeout in seconds.
+/// Default per-cell timeout in seconds; `--timeout-ms` overrides it
+/// through the Sandbox.
 pub const CELL_TIMEOUT_SECS: u64 = 2;
 
 /// Max concurrent threads (for work-stealing across contexts).
@@ -191,6 +203,7 @@ pub fn run_grid(
     }
 
     let completed = AtomicUsize::new(0);
+    let peak_rss_kb = AtomicU64::new(0);
     let grid_start = std::time::Instant::now();
 
     // Work-stealing: threads dequeue contexts from a shared queue.
@@ -199,13 +212,17 @@ pub fn run_grid(
     // while another thread processes a slow context.
     use std::sync::Mutex;
     let work_queue = Mutex::new(cells_by_ctx.iter());
+    // Archives are checked once for the grid, then only extracted per cell
+    let checked_archives = Mutex::new(HashSet::new());
 
     let results: Vec<_> = std::thread::scope(|s| {
         let n_threads = MAX_THREADS.min(cells_by_ctx.len()).max(1);
 
         let handles: Vec<_> = (0..n_threads).map(|_| {
             let completed = &completed;
+            let peak_rss_kb = &peak_rss_kb;
             let work_queue = &work_queue;
+            let checked_archives = &checked_archives;
             s.spawn(move || {
                 let mut results: Vec<(String, usize, Result<Observation, String>)> = Vec::new();
 
@@ -218,6 +235,8 @@ pub fn run_grid(
 
                 let batch_dir = match tempfile::Builder::new()
                     .prefix("bgrid_batch_")
+                    // Traced cell command lines refer into it
+                    .disable_cleanup(sandbox.verbose())
                     .tempdir() {
                     Ok(d) => d,
                     Err(e) => {
@@ -244,11 +263,11 @@ pub fn run_grid(
                     // Set up first cell to get env vars and evaluate extracts
                     let first_cell_dir = batch_dir.path().join(format!("c{}", global_cell_idx));
                     let _ = std::fs::create_dir(&first_cell_dir);
-                    let env_vars = match sandbox::apply_setup(&first_cell_dir, binary, &ctx.commands, probe_dir, sandbox) {
+                    let env_vars = match sandbox::apply_setup(&first_cell_dir, binary, &ctx.commands, probe_dir, sandbox, checked_archives) {
                         Ok(env) => env,
                         Err(e) => {
                             for cell in ctx_cells {
-                                results.push((ctx.name.clone(), cell.run_index, Err(format!("{}", e))));
+                                results.push((ctx.name.clone(), cell.run_index, Err(format!("{:#}", e))));
                             }
                             continue;
                         }
@@ -283,15 +302,6 @@ pub fn run_grid(
                     let env_prefix = if env_str.is_empty() { String::new() }
                         else { format!("{} ", env_str) };
 
-                    let stdin_part = match &ctx.stdin {
-                        Some(crate::parse::StdinSource::Lines(lines)) => {
-                            let content = lines.join("\n");
-                            format!("printf '{}' | ", content.replace('\'', "'\\''"))
-                        }
-                        Some(crate::parse::StdinSource::FromFile(_)) => String::new(), // handled per-cell below
-                        None => String::new(),
-                    };
-
                     for (local_idx, cell) in ctx_cells.iter().enumerate() {
                         let cell_idx = global_cell_idx;
 
@@ -299,8 +309,8 @@ pub fn run_grid(
                         if local_idx > 0 {
                             let cell_dir = batch_dir.path().join(format!("c{}", cell_idx));
                             let _ = std::fs::create_dir(&cell_dir);
-                            if let Err(e) = sandbox::apply_setup(&cell_dir, binary, &ctx.commands, probe_dir, sandbox) {
-                                results.push((ctx.name.clone(), cell.run_index, Err(format!("{}", e))));
+                            if let Err(e) = sandbox::apply_setup(&cell_dir, binary, &ctx.commands, probe_dir, sandbox, checked_archives) {
+                                results.push((ctx.name.clone(), cell.run_index, Err(format!("{:#}", e))));
                                 global_cell_idx += 1;
                                 continue;
                             }
@@ -321,18 +331,32 @@ pub fn run_grid(
                             .collect::<Vec<_>>()
                             .join(" ");
 
-                        // Per-cell stdin for FromFile variant
-                        let cell_stdin = if let Some(crate::parse::StdinSource::FromFile(path)) = &ctx.stdin {
-                            format!("cat /batch/c{}/{} | ", cell_idx, shell_escape(path))
-                        } else {
-                            stdin_part.clone()
-                        };
+                        // Run-level stdin overrides the context's
+                        let stdin_source = run.stdin.as_ref().or(ctx.stdin.as_ref());
+                        let cell_stdin = stdin_prefix(stdin_source, &format!("/batch/c{}", cell_idx));
+
+                        if sandbox.verbose() {
+                            // Standalone form: extracts are evaluated inline in the cell dir
+                            let cell_dir = batch_dir.path().join(format!("c{}", cell_idx));
+                            let host_dir = cell_dir.to_string_lossy();
+                            let trace_args = run.args.iter()
+                                .map(|a| match a {
+                                    crate::parse::Arg::Literal(s) => shell_escape(s),
+                                    crate::parse::Arg::Extract(e) => {
+                                        format!("\"$(cd {} && {})\"", shell_escape(&host_dir), e)
+                                    }
+                                })
+                                .collect::<Vec<_>>()
+                                .join(" ");
+                            sandbox.trace_cell(&cell_dir, &env_vars, binary, &trace_args,
+                                &stdin_prefix(stdin_source, &host_dir));
+                        }
 
                         // Background each cell with & for parallel execution within bwrap.
                         // Concurrency limited by periodic `wait` every PAR cells.
                         script_content.push_str(&format!(
-                            "(cd /batch/c{ci} && {stdin}timeout {t} {env}{bin}{args}>/batch/out/{ci}.out 2>/batch/out/{ci}.err; echo $? >/batch/out/{ci}.rc) &\n",
-                            ci = cell_idx, stdin = cell_stdin, t = CELL_TIMEOUT_SECS,
+                            "(cd /batch/c{ci} && {stdin}timeout {t} {env}{bin}{args}>/batch/out/{ci}.out 2>/batch/out/{ci}.err; echo $? >/batch/out/{ci}.rc; times >/batch/out/{ci}.times) &\n",
+                            ci = cell_idx, stdin = cell_stdin, t = sandbox.timeout_arg(),
                             env = env_prefix, bin = shell_escape(binary),
                             args = if args_str.is_empty() { String::new() } else { format!(" {}", args_str) },
                         ));
@@ -365,16 +389,18 @@ pub fn run_grid(
                     unsafe { cmd.pre_exec(|| { libc::setpgid(0, 0); Ok(()) }); }
                 }
 
-                let batch_timeout = CELL_TIMEOUT_SECS * (global_cell_idx as u64 + 1);
+                let batch_timeout_ms = sandbox.batch_timeout_ms(global_cell_idx);
                 let child = cmd.spawn();
                 match child {
                     Ok(mut child) => {
                         let child_id = child.id();
                         let timer = std::thread::spawn(move || {
-                            std::thread::sleep(std::time::Duration::from_secs(batch_timeout));
+                            std::thread::sleep(std::time::Duration::from_millis(batch_timeout_ms));
                             unsafe { libc::kill(-(child_id as i32), libc::SIGKILL); }
                         });
-                        let _ = child.wait();
+                        if let Some(kb) = wait_max_rss(&mut child) {
+                            peak_rss_kb.fetch_max(kb, Ordering::Relaxed);
+                        }
                         drop(timer);
                     }
                     Err(e) => {
@@ -387,25 +413,26 @@ pub fn run_grid(
 
                 // Read results for all cells in this thread's batch
                 for (cell_idx, (ctx_name, ri, before)) in cell_data.into_iter().enumerate() {
-                    let stdout = std::fs::read_to_string(out_dir.join(format!("{}.out", cell_idx)))
-                        .unwrap_or_default();
-                    let stderr = std::fs::read_to_string(out_dir.join(format!("{}.err", cell_idx)))
-                        .unwrap_or_default();
+                    let (stdout, binary_stdout) = read_output(&out_dir.join(format!("{}.out", cell_idx)));
+                    let (stderr, _) = read_output(&out_dir.join(format!("{}.err", cell_idx)));
                     let exit_str = std::fs::read_to_string(out_dir.join(format!("{}.rc", cell_idx)))
                         .unwrap_or_default();
                     let exit_code: Option<i32> = exit_str.trim().parse().ok();
+                    let cpu_time_ms = std::fs::read_to_string(out_dir.join(format!("{}.times", cell_idx)))
+                        .ok()
+                        .and_then(|t| parse_times(&t));
 
                     let cell_dir = batch_dir.path().join(format!("c{}", cell_idx));
                     let after = snapshot_fs(&cell_dir);
                     let fs_changes = diff_snapshots(&before, &after);
 
                     let wall_time_ms = if exit_code == Some(137) || exit_code == Some(-1) {
-                        CELL_TIMEOUT_SECS * 1000
+                        sandbox.cell_timeout_ms()
                     } else { 0 };
 
                     results.push((ctx_name, ri, Ok(Observation {
-                        stdout, stderr, exit_code, fs_changes,
-                        resources: ResourceUsage { wall_time_ms },
+                        stdout, binary_stdout, stderr, exit_code, fs_changes,
+                        resources: ResourceUsage { wall_time_ms, cpu_time_ms },
                     })));
 
                     let done = completed.fetch_add(1, Ordering::Relaxed) + 1;
@@ -434,11 +461,13 @@ pub fn run_grid(
     let mut cells: HashMap<(String, usize), Observation> = HashMap::new();
     let mut setup_failures: HashMap<String, String> = HashMap::new();
     let mut timeout_count = 0usize;
+    let mut cpu_ms = 0u64;
 
     for (ctx_name, ri, result) in results {
         match result {
             Ok(obs) => {
-                if obs.resources.wall_time_ms >= (CELL_TIMEOUT_SECS * 1000 - 100) {
+                cpu_ms += obs.resources.cpu_time_ms.unwrap_or(0);
+                if obs.resources.wall_time_ms >= sandbox.cell_timeout_ms().saturating_sub(100).max(1) {
                     timeout_count += 1;
                 }
                 cells.insert((ctx_name, ri), obs);
@@ -452,18 +481,90 @@ pub fn run_grid(
     } else {
         total_cells as u64
     };
-    eprintln!("  grid: {} cells in {:.1}s ({} cells/s, {} timeouts)",
-        total_cells, grid_elapsed.as_secs_f64(), cells_per_sec, timeout_count);
+    let peak_rss_kb = Some(peak_rss_kb.into_inner()).filter(|&kb| kb > 0);
+    eprintln!("  grid: {} cells in {:.1}s ({} cells/s, {} timeouts at {}ms, {:.1}s cpu, {} MiB peak rss)",
+        total_cells, grid_elapsed.as_secs_f64(), cells_per_sec, timeout_count, sandbox.cell_timeout_ms(),
+        cpu_ms as f64 / 1000.0, peak_rss_kb.unwrap_or(0) / 1024);
 
     Ok(GridResult {
         cells,
         setup_failures,
         context_count: script.contexts.len(),
+        peak_rss_kb,
     })
 }
 
 use crate::sandbox::shell_escape;
 
+/// Wait for a batch's bwrap process and return its peak RSS in KiB.
+/// wait4's rusage folds in every reaped descendant, so ru_maxrss is the
+/// largest process anywhere in the batch (Linux reports it in KiB).
+fn wait_max_rss(child: &mut std::process::Child) -> Option<u64> {
+    let pid = child.id() as libc::pid_t;
+    let mut status = 0;
+    // SAFETY: rusage is plain old data; all-zero is a valid value
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    loop {
+        // SAFETY: pid is our own unreaped child; pointers are to live locals
+        let r = unsafe { libc::wait4(pid, &mut status, 0, &mut usage) };
+        if r == pid {
+            return u64::try_from(usage.ru_maxrss).ok().filter(|&kb| kb > 0);
+        }
+        if std::io::Error::last_os_error().kind() != std::io::ErrorKind::Interrupted {
+            let _ = child.wait();
+            return None;
+        }
+    }
+}
+
+/// Read a captured output stream. Non-UTF-8 output is kept, escaped, with
+/// its byte length; reading it as a String would drop it as empty.
+fn read_output(path: &Path) -> (String, Option<usize>) {
+    let bytes = std::fs::read(path).unwrap_or_default();
+    match String::from_utf8(bytes) {
+        Ok(text) => (text, None),
+        Err(e) => {
+            let bytes = e.into_bytes();
+            let mut text = String::with_capacity(bytes.len());
+            for chunk in bytes.utf8_chunks() {
+                text.push_str(chunk.valid());
+                for b in chunk.invalid() {
+                    text.push_str(&format!("\\x{:02x}", b));
+                }
+            }
+            (text, Some(bytes.len()))
+        }
+    }
+}
+
+/// Children's user + system time in ms from POSIX `times` output. The
+/// second line holds the children's times, e.g. `0m0.012000s 0m0.004000s`
+/// (dash) or `0m0.012s 0m0.004s` (bash).
+fn parse_times(text: &str) -> Option<u64> {
+    let line = text.lines().nth(1)?;
+    let mut total = 0.0;
+    for field in line.split_whitespace() {
+        let (min, sec) = field.strip_suffix('s')?.split_once('m')?;
+        total += min.parse::<f64>().ok()? * 60.0 + sec.parse::<f64>().ok()?;
+    }
+    Some((total * 1000.0).round() as u64)
+}
+
+/// Shell prefix that feeds a cell's stdin. `from` paths are workspace-relative,
+/// resolved against `cell_dir` (as seen by the shell running the prefix).
+fn stdin_prefix(source: Option<&StdinSource>, cell_dir: &str) -> String {
+    match source {
+        Some(StdinSource::Lines(lines)) => {
+            let content = lines.join("\n");
+            format!("printf '{}' | ", content.replace('\'', "'\\''"))
+        }
+        Some(StdinSource::FromFile(path)) => {
+            format!("cat {} | ", shell_escape(&format!("{}/{}", cell_dir, path)))
+        }
+        None => String::new(),
+    }
+}
+
 /// Group observations by identical output. Returns (context_names, representative_obs) groups.
 pub fn collapse<'a>(
     obs_list: &[(&'a str, &'a Observation)],
@@ -596,3 +697,36 @@ pub fn validate_from_references(script: &Script) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_times() {
+        assert_eq!(parse_times("0m0.000000s 0m0.000000s\n0m0.012000s 0m0.004000s\n"), Some(16));
+        assert_eq!(parse_times("0m0.000s 0m0.000s\n1m2.500s 0m0.250s\n"), Some(62750));
+        assert_eq!(parse_times("0m0.000s 0m0.000s\n"), None);
+        assert_eq!(parse_times(""), None);
+    }
+
+    #[test]
+    fn test_read_output_keeps_binary() {
+        let dir = tempfile::tempdir().unwrap();
+        let text = dir.path().join("t.out");
+        std::fs::write(&text, "hello\n").unwrap();
+        assert_eq!(read_output(&text), ("hello\n".to_string(), None));
+
+        let bin = dir.path().join("b.out");
+        std::fs::write(&bin, b"\x1f\x8b\x08ok\xff").unwrap();
+        assert_eq!(read_output(&bin), ("\u{1f}\\x8b\u{8}ok\\xff".to_string(), Some(6)));
+
+        assert_eq!(read_output(&dir.path().join("missing")), (String::new(), None));
+    }
+
+    #[test]
+    fn test_wait_max_rss_reaps_child() {
+        let mut child = std::process::Command::new("sh").arg("-c").arg("exit 3").spawn().unwrap();
+        assert!(wait_max_rss(&mut child).is_some_and(|kb| kb > 0));
+    }
+}
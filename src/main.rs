@@ -8,13 +8,63 @@ fn main() -> Result<()> {
     let args: Vec<String> = std::env::args().collect();
 
     let dry_run = args.iter().any(|a| a == "--dry-run");
+    let skeleton = args.iter().any(|a| a == "--skeleton");
+    let show_config = args.iter().any(|a| a == "--show-config");
+    let json_compact = args.iter().any(|a| a == "--json-compact");
+    let verbose = args.iter().any(|a| a == "--verbose");
+    let provenance = args.iter().any(|a| a == "--provenance");
+    let require_success = args.iter().any(|a| a == "--require-success");
+    let summary_path = option_value(&args, "summary").map(PathBuf::from);
+    let help_flag = option_value(&args, "help-flag");
+    let uid = id_option(&args, "uid")?;
+    let gid = id_option(&args, "gid")?;
+    let timeout_ms = option_value(&args, "timeout-ms")
+        .map(|v| v.parse::<u64>().with_context(|| format!("--timeout-ms must be a number of milliseconds (got {:?})", v)))
+        .transpose()?;
+    let limits = output::PreviewLimits {
+        stdout_lines: line_limit_option(&args, "stdout-lines")?
+            .unwrap_or(output::PreviewLimits::default().stdout_lines),
+        stderr_lines: line_limit_option(&args, "stderr-lines")?
+            .unwrap_or(output::PreviewLimits::default().stderr_lines),
+    };
+    let color = match option_value(&args, "color") {
+        Some(v) => output::ColorChoice::parse(v)
+            .with_context(|| format!("--color must be auto, always, or never (got {:?})", v))?,
+        None => output::ColorChoice::Auto,
+    };
     let positional: Vec<&String> = args.iter().skip(1).filter(|a| !a.starts_with("--")).collect();
 
+    if show_config {
+        // A missing bwrap is reported in the config rather than failing it
+        let bwrap = sandbox::find_bwrap();
+        let sandbox = sandbox::Sandbox::at(bwrap.as_ref().cloned().unwrap_or_default())
+            .with_verbose(verbose).with_identity(uid, gid)?
+            .with_cell_timeout_ms(timeout_ms)?;
+        let opts = ExploreOptions { color, provenance, require_success, summary_path, help_flag, json_compact };
+        let bwrap_error = bwrap.err().map(|e| e.to_string());
+        let config = format_config(&sandbox, bwrap_error.as_deref(), &opts, verbose, limits);
+        print!("{}", if json_compact { report::compact_json(&config) } else { config });
+        return Ok(());
+    }
+
     if positional.is_empty() {
         eprintln!("Usage: bgrid [options] <binary> [<probe-file>]");
         eprintln!("       bgrid <binary>                            explore: discover + run");
         eprintln!("       bgrid <binary> <file.probe>               run observation grid");
         eprintln!("       bgrid --dry-run <binary> <file.probe>     show grid without executing");
+        eprintln!("       bgrid --dry-run <binary>                  discover only: show generated design");
+        eprintln!("       bgrid --skeleton <binary>                 print a probe skeleton for manual authoring");
+        eprintln!("       bgrid --show-config [options]             print the effective settings as JSON");
+        eprintln!("options: --verbose                             print each bwrap command line");
+        eprintln!("         --color=auto|always|never             colorize the report on a terminal");
+        eprintln!("         --provenance                          append bgrid version, binary hash, date");
+        eprintln!("         --require-success                     count a flag as observed only from exit-0 runs");
+        eprintln!("         --uid=N --gid=N                       run the binary as this uid/gid in the sandbox");
+        eprintln!("         --timeout-ms=N                        per-invocation time limit (default 2000)");
+        eprintln!("         --summary=PATH                        also write a JSON summary of the exploration");
+        eprintln!("         --stdout-lines=N --stderr-lines=N     lines per stream in .results (N or all)");
+        eprintln!("         --json-compact                        write JSON on one line instead of indented");
+        eprintln!("         --help-flag=FLAG                      read help only from FLAG (default: --help, -h, help, no args)");
         std::process::exit(1);
     }
 
@@ -25,16 +75,103 @@ fn main() -> Result<()> {
         if dry_run {
             cmd_dry_run(&test_path)
         } else {
-            let sandbox = sandbox::Sandbox::new()?;
-            cmd_run(binary, &test_path, &sandbox)
+            let sandbox = sandbox::Sandbox::new()?.with_verbose(verbose).with_identity(uid, gid)?
+                .with_cell_timeout_ms(timeout_ms)?;
+            cmd_run(binary, &test_path, &sandbox, provenance, help_flag, limits)
         }
     } else {
-        let sandbox = sandbox::Sandbox::new()?;
-        cmd_discover(&positional, &sandbox)
+        let sandbox = sandbox::Sandbox::new()?.with_verbose(verbose).with_identity(uid, gid)?
+            .with_cell_timeout_ms(timeout_ms)?;
+        if skeleton {
+            cmd_skeleton(&positional, &sandbox, help_flag)
+        } else if dry_run {
+            cmd_design(&positional, &sandbox, help_flag)
+        } else {
+            let opts = ExploreOptions { color, provenance, require_success, summary_path, help_flag, json_compact };
+            cmd_discover(&positional, &sandbox, &opts)
+        }
+    }
+}
+
+/// Value of a `--name=value` option, if present.
+fn option_value<'a>(args: &'a [String], name: &str) -> Option<&'a str> {
+    args.iter().find_map(|a| a.strip_prefix("--")?.strip_prefix(name)?.strip_prefix('='))
+}
+
+/// Parse a numeric `--name=N` id option.
+fn id_option(args: &[String], name: &str) -> Result<Option<u32>> {
+    option_value(args, name)
+        .map(|v| v.parse::<u32>().with_context(|| format!("--{} must be a numeric id (got {:?})", name, v)))
+        .transpose()
+}
+
+/// Parse a `--name=N|all` line limit: Some(None) for `all`, None if absent.
+fn line_limit_option(args: &[String], name: &str) -> Result<Option<Option<usize>>> {
+    let Some(v) = option_value(args, name) else { return Ok(None) };
+    if v == "all" {
+        return Ok(Some(None));
+    }
+    match v.parse::<usize>() {
+        Ok(n) if n > 0 => Ok(Some(Some(n))),
+        _ => anyhow::bail!("--{} must be a positive number of lines or \"all\" (got {:?})", name, v),
     }
 }
 
-fn cmd_discover(command: &[&String], sandbox: &sandbox::Sandbox) -> Result<()> {
+/// Explore-mode output settings from the command line.
+struct ExploreOptions<'a> {
+    color: output::ColorChoice,
+    provenance: bool,
+    require_success: bool,
+    summary_path: Option<PathBuf>,
+    help_flag: Option<&'a str>,
+    json_compact: bool,
+}
+
+/// Effective settings after defaults, command-line options, and the
+/// environment (NO_COLOR, whether stdout is a terminal), as JSON.
+fn format_config(
+    sandbox: &sandbox::Sandbox,
+    bwrap_error: Option<&str>,
+    opts: &ExploreOptions<'_>,
+    verbose: bool,
+    limits: output::PreviewLimits,
+) -> String {
+    let lines = |n: Option<usize>| n.map(|n| n.to_string()).unwrap_or_else(|| "null".into());
+    let help_sources: Vec<String> = match opts.help_flag {
+        Some(flag) => vec![report::json_string(flag)],
+        None => discover::HELP_SOURCES.iter()
+            .map(|s| report::json_string(if s.is_empty() { "(no args)" } else { s[0] }))
+            .collect(),
+    };
+    let summary = opts.summary_path.as_ref()
+        .map(|p| report::json_string(&p.to_string_lossy()))
+        .unwrap_or_else(|| "null".into());
+
+    let mut out = String::from("{\n");
+    out.push_str(&format!("  \"version\": {},\n", report::json_string(env!("CARGO_PKG_VERSION"))));
+    let (bwrap, bwrap_error) = match bwrap_error {
+        None => (report::json_string(&sandbox.bwrap_path().to_string_lossy()), "null".to_string()),
+        Some(e) => ("null".to_string(), report::json_string(e)),
+    };
+    out.push_str(&format!("  \"bwrap\": {},\n", bwrap));
+    out.push_str(&format!("  \"bwrap_error\": {},\n", bwrap_error));
+    out.push_str(&format!("  \"uid\": {},\n", sandbox.uid()));
+    out.push_str(&format!("  \"gid\": {},\n", sandbox.gid()));
+    out.push_str(&format!("  \"timeout_ms\": {},\n", sandbox.cell_timeout_ms()));
+    out.push_str(&format!("  \"help_sources\": [{}],\n", help_sources.join(", ")));
+    out.push_str(&format!("  \"color\": {},\n", report::json_string(opts.color.label())));
+    out.push_str(&format!("  \"color_enabled\": {},\n", opts.color.enabled()));
+    out.push_str(&format!("  \"verbose\": {},\n", verbose));
+    out.push_str(&format!("  \"provenance\": {},\n", opts.provenance));
+    out.push_str(&format!("  \"require_success\": {},\n", opts.require_success));
+    out.push_str(&format!("  \"stdout_lines\": {},\n", lines(limits.stdout_lines)));
+    out.push_str(&format!("  \"stderr_lines\": {},\n", lines(limits.stderr_lines)));
+    out.push_str(&format!("  \"summary\": {}\n", summary));
+    out.push_str("}\n");
+    out
+}
+
+fn cmd_discover(command: &[&String], sandbox: &sandbox::Sandbox, opts: &ExploreOptions<'_>) -> Result<()> {
     let binary = command[0].as_str();
     let sub_args: Vec<&str> = command[1..].iter().map(|s| s.as_str()).collect();
 
@@ -47,8 +184,8 @@ fn cmd_discover(command: &[&String], sandbox: &sandbox::Sandbox) -> Result<()> {
 
     // Single-phase exploration: fixed DoE design (no iterative refinement).
     // All single-flag and pairwise-combo runs are generated up front.
-    let (script, flag_info) = discover::generate_initial_script(binary, &sub_args, sandbox)?;
-    eprintln!("=== Exploring {} ===", cmd_label);
+    let (script, flag_info) = discover::generate_initial_script(binary, &sub_args, sandbox, opts.help_flag)?;
+    eprintln!("=== Exploring {} ({}, timeout {}ms) ===", cmd_label, sandbox.identity(), sandbox.cell_timeout_ms());
     eprintln!("{} contexts, {} runs, {} cells",
         script.contexts.len(), script.runs.len(), execute::count_cells(&script));
 
@@ -88,7 +225,7 @@ fn cmd_discover(command: &[&String], sandbox: &sandbox::Sandbox) -> Result<()> {
 
     let all_runs: Vec<&analyze::RunAnalysis> = metrics.runs.iter().collect();
     let t_report = std::time::Instant::now();
-    let report = report::format_exploration_report(
+    let (mut report, summary) = report::format_exploration_report(
         &rounds,
         &metrics,
         Some(&flag_info),
@@ -96,11 +233,29 @@ fn cmd_discover(command: &[&String], sandbox: &sandbox::Sandbox) -> Result<()> {
         &cmd_label,
         &all_runs,
         &script.contexts,
+        opts.require_success,
     );
+    if opts.provenance {
+        report.push_str(&report::format_provenance(binary));
+    }
     let report_elapsed = t_report.elapsed();
     eprintln!("  timing: analysis={}ms report={}ms",
         analysis_elapsed.as_millis(), report_elapsed.as_millis());
-    print!("{}", report);
+    if opts.color.enabled() {
+        print!("{}", output::colorize_report(&report));
+    } else {
+        print!("{}", report);
+    }
+
+    if let Some(path) = &opts.summary_path {
+        let mut json = report::format_summary_json(&cmd_label, binary, grid.cells.len(), grid.peak_rss_kb, &metrics, &summary);
+        if opts.json_compact {
+            json = report::compact_json(&json);
+        }
+        std::fs::write(path, json)
+            .with_context(|| format!("write {}", path.display()))?;
+        eprintln!("wrote {}", path.display());
+    }
 
     Ok(())
 }
@@ -141,7 +296,33 @@ fn load_script(test_path: &PathBuf) -> Result<parse::Script> {
 
 fn cmd_dry_run(test_path: &PathBuf) -> Result<()> {
     let script = load_script(test_path)?;
+    print_script(&script);
+    execute::validate_from_references(&script);
+    Ok(())
+}
+
+/// Explore-mode dry run: run discovery (help parsing + value probing) and
+/// print the resulting fixed design, without executing the grid.
+fn cmd_design(command: &[&String], sandbox: &sandbox::Sandbox, help_flag: Option<&str>) -> Result<()> {
+    let binary = command[0].as_str();
+    let sub_args: Vec<&str> = command[1..].iter().map(|s| s.as_str()).collect();
+    let (script, flag_info) = discover::generate_initial_script(binary, &sub_args, sandbox, help_flag)?;
+    eprintln!("discovered {} flags", flag_info.flags.len());
+    print_script(&script);
+    Ok(())
+}
 
+/// Print a probe skeleton for the binary: one context, a base run, and
+/// one run per discovered flag, ready to edit into a probe file.
+fn cmd_skeleton(command: &[&String], sandbox: &sandbox::Sandbox, help_flag: Option<&str>) -> Result<()> {
+    let binary = command[0].as_str();
+    let sub_args: Vec<&str> = command[1..].iter().map(|s| s.as_str()).collect();
+    print!("{}", discover::generate_skeleton(binary, &sub_args, sandbox, help_flag)?);
+    Ok(())
+}
+
+/// Print contexts, runs, and grid size of a resolved script.
+fn print_script(script: &parse::Script) {
     println!("contexts:");
     for ctx in &script.contexts {
         println!("  {:?} ({} commands)", ctx.name, ctx.commands.len());
@@ -161,46 +342,60 @@ fn cmd_dry_run(test_path: &PathBuf) -> Result<()> {
             Some(ref_args) => format!(" from {}", output::format_args(ref_args)),
             None => String::new(),
         };
-        println!("  [{}] {}{}{}", i, args, from, scope);
+        let stdin = match &run.stdin {
+            Some(parse::StdinSource::Lines(lines)) => format!(" stdin {}", lines.iter().map(|l| format!("{:?}", l)).collect::<Vec<_>>().join(" ")),
+            Some(parse::StdinSource::FromFile(path)) => format!(" stdin from {:?}", path),
+            None => String::new(),
+        };
+        println!("  [{}] {}{}{}{}", i, args, from, scope, stdin);
     }
 
-    let cells = execute::count_cells(&script);
+    let cells = execute::count_cells(script);
     println!("\ngrid: {} contexts x {} runs = {} cells", script.contexts.len(), script.runs.len(), cells);
-
-    execute::validate_from_references(&script);
-
-    Ok(())
 }
 
-fn cmd_run(binary: &str, test_path: &PathBuf, sandbox: &sandbox::Sandbox) -> Result<()> {
+fn cmd_run(
+    binary: &str,
+    test_path: &PathBuf,
+    sandbox: &sandbox::Sandbox,
+    provenance: bool,
+    help_flag: Option<&str>,
+    limits: output::PreviewLimits,
+) -> Result<()> {
     let script = load_script(test_path)?;
 
     execute::validate_from_references(&script);
 
     let actual_cells = execute::count_cells(&script);
     eprintln!(
-        "{} contexts, {} runs, {} cells",
-        script.contexts.len(), script.runs.len(), actual_cells
+        "{} contexts, {} runs, {} cells ({}, timeout {}ms)",
+        script.contexts.len(), script.runs.len(), actual_cells, sandbox.identity(), sandbox.cell_timeout_ms()
     );
 
     let probe_dir = test_path.parent().unwrap_or(std::path::Path::new("."));
     let grid = execute::run_grid(binary, &script, probe_dir, sandbox)?;
 
-    let flag_info = discover::try_help(binary, &[], sandbox)
+    let flag_info = discover::try_help(binary, &[], sandbox, help_flag)
         .map(|text| discover::extract_flag_info(&text))
         .ok();
 
     let metrics = analyze::analyze(&script, &grid, flag_info.as_ref(), None);
 
     let probe_name = test_path.file_name().unwrap_or_default().to_string_lossy();
-    let out = report::format_run_report(
+    let mut out = report::format_run_report(
         &metrics,
         flag_info.as_ref(),
         &probe_name,
         grid.cells.len(),
         &grid.setup_failures,
+        grid.peak_rss_kb,
+        limits,
     );
 
+    if provenance {
+        out.push_str(&report::format_provenance(binary));
+    }
+
     // Write results file
     let results_path = test_path.with_extension("results");
     std::fs::write(&results_path, &out)
@@ -66,22 +66,59 @@ pub fn format_context_group(names: &[&str], total: usize) -> String {
     }
 }
 
+/// Lines of stdout shown per observation before truncating, by default.
+pub const STDOUT_PREVIEW_LINES: usize = 20;
+
+/// How many lines of each stream format_obs shows; None shows all of it.
+/// Set from `--stdout-lines` / `--stderr-lines`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PreviewLimits {
+    pub stdout_lines: Option<usize>,
+    pub stderr_lines: Option<usize>,
+}
+
+impl Default for PreviewLimits {
+    /// First STDOUT_PREVIEW_LINES of stdout, all of stderr.
+    fn default() -> Self {
+        PreviewLimits { stdout_lines: Some(STDOUT_PREVIEW_LINES), stderr_lines: None }
+    }
+}
+
+/// Marker for elided output, naming the stream and how much was cut:
+/// `... [stderr truncated: 3 more lines, 142 bytes]`.
+pub fn truncation_marker(stream: &str, elided: &[&str]) -> String {
+    let bytes: usize = elided.iter().map(|l| l.len() + 1).sum();
+    let noun = if elided.len() == 1 { "line" } else { "lines" };
+    format!("... [{} truncated: {} more {}, {} bytes]", stream, elided.len(), noun, bytes)
+}
+
 /// Format a single observation's output.
-pub fn format_obs(out: &mut String, obs: &Observation, indent: &str) {
+pub fn format_obs(out: &mut String, obs: &Observation, indent: &str, limits: PreviewLimits) {
     let stdout_lines: Vec<&str> = obs.stdout.lines().collect();
-    if stdout_lines.is_empty() {
+    if let Some(n) = obs.binary_stdout {
+        out.push_str(&format!("{}stdout: {}\n", indent, binary_placeholder(n)));
+    } else if stdout_lines.is_empty() {
         out.push_str(&format!("{}stdout: (empty)\n", indent));
     } else {
         out.push_str(&format!("{}stdout ({} lines):\n", indent, stdout_lines.len()));
-        for line in stdout_lines.iter().take(20) {
+        let shown = limits.stdout_lines.unwrap_or(usize::MAX).min(stdout_lines.len());
+        for line in &stdout_lines[..shown] {
             out.push_str(&format!("{}  {}\n", indent, line));
         }
-        if stdout_lines.len() > 20 {
-            out.push_str(&format!("{}  ... ({} more)\n", indent, stdout_lines.len() - 20));
+        if shown < stdout_lines.len() {
+            out.push_str(&format!("{}  {}\n", indent,
+                truncation_marker("stdout", &stdout_lines[shown..])));
         }
     }
-    if !obs.stderr.trim().is_empty() {
-        out.push_str(&format!("{}stderr: {}\n", indent, obs.stderr.trim()));
+    let stderr_lines: Vec<&str> = obs.stderr.trim().lines().collect();
+    if !stderr_lines.is_empty() {
+        let shown = limits.stderr_lines.unwrap_or(usize::MAX).min(stderr_lines.len());
+        out.push_str(&format!("{}stderr: {}\n", indent,
+            stderr_lines[..shown].join(&format!("\n{}  ", indent))));
+        if shown < stderr_lines.len() {
+            out.push_str(&format!("{}  {}\n", indent,
+                truncation_marker("stderr", &stderr_lines[shown..])));
+        }
     }
     out.push_str(&format!("{}exit: {}\n", indent, format_exit(obs.exit_code.unwrap_or(-1))));
     if !obs.fs_changes.is_empty() {
@@ -102,6 +139,11 @@ pub fn format_obs(out: &mut String, obs: &Observation, indent: &str) {
     }
 }
 
+/// Stand-in for non-UTF-8 stdout, which would only print as escapes.
+pub fn binary_placeholder(bytes: usize) -> String {
+    format!("[binary output, {} bytes]", bytes)
+}
+
 /// Strip ANSI escape sequences from a string for readable report output.
 pub fn strip_ansi(s: &str) -> String {
     let mut out = String::with_capacity(s.len());
@@ -118,6 +160,91 @@ pub fn strip_ansi(s: &str) -> String {
     out
 }
 
+/// When to emit ANSI color in terminal output (`--color=auto|always|never`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "auto" => Some(ColorChoice::Auto),
+            "always" => Some(ColorChoice::Always),
+            "never" => Some(ColorChoice::Never),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ColorChoice::Auto => "auto",
+            ColorChoice::Always => "always",
+            ColorChoice::Never => "never",
+        }
+    }
+
+    /// Auto colors only a terminal stdout, and honors NO_COLOR.
+    pub fn enabled(self) -> bool {
+        use std::io::IsTerminal;
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+                    && std::io::stdout().is_terminal()
+            }
+        }
+    }
+}
+
+/// Colorize an exploration report for terminal display: section headers
+/// by verdict, plus ANOMALY/UNEXPLAINED/ALIAS markers. Applied at print
+/// time only, so files written by bgrid never contain escape codes.
+pub fn colorize_report(report: &str) -> String {
+    const BOLD: &str = "\x1b[1m";
+    const GREEN: &str = "\x1b[1;32m";
+    const YELLOW: &str = "\x1b[1;33m";
+    const RED: &str = "\x1b[1;31m";
+    const CYAN: &str = "\x1b[36m";
+    const RESET: &str = "\x1b[0m";
+
+    let mut out = String::with_capacity(report.len());
+    for line in report.split_inclusive('\n') {
+        let text = line.trim_end_matches('\n');
+        let trimmed = text.trim_start();
+        let color = if text.starts_with("## Verified") || text.starts_with("## Distinguishable") {
+            Some(GREEN)
+        } else if text.starts_with("## Recognized") {
+            Some(YELLOW)
+        } else if text.starts_with("## Indistinguishable") || text.starts_with("## Untested") {
+            Some(RED)
+        } else if text.starts_with('#') {
+            Some(BOLD)
+        } else if trimmed.starts_with("ANOMALY:") {
+            Some(RED)
+        } else if trimmed.starts_with("UNEXPLAINED") {
+            Some(YELLOW)
+        } else if trimmed.starts_with("ALIAS:") {
+            Some(CYAN)
+        } else {
+            None
+        };
+        match color {
+            Some(c) if !text.is_empty() => {
+                out.push_str(c);
+                out.push_str(text);
+                out.push_str(RESET);
+                out.push_str(&line[text.len()..]);
+            }
+            _ => out.push_str(line),
+        }
+    }
+    out
+}
+
 /// Format a SetupCommand for display in skeleton/diagnostic output.
 pub fn format_setup_cmd(cmd: &SetupCommand) -> String {
     match cmd {
@@ -153,5 +280,6 @@ pub fn format_setup_cmd(cmd: &SetupCommand) -> String {
             let quoted: Vec<String> = args.iter().map(|a| format!("\"{}\"", a)).collect();
             format!("invoke {}", quoted.join(" "))
         }
+        SetupCommand::Archive { src, into } => format!("archive \"{}\" into \"{}\"", src, into),
     }
 }
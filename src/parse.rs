@@ -32,6 +32,9 @@ pub enum SetupCommand {
     Remove { path: String },
     RemoveEnv { var: String },
     Invoke { args: Vec<String> },
+    /// Unpack a tar archive (any compression tar detects) into `into`,
+    /// relative to the workspace.
+    Archive { src: String, into: String },
 }
 
 #[derive(Debug, Clone)]
@@ -122,9 +125,11 @@ pub struct Run {
     pub in_contexts: Option<Vec<String>>,
     /// If set, diff this run's results from the reference run.
     pub diff_from: Option<Vec<Arg>>,
+    /// Run-level stdin; overrides the context's stdin when set.
+    pub stdin: Option<StdinSource>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum StdinSource {
     Lines(Vec<String>),
     FromFile(String),
@@ -236,8 +241,8 @@ pub fn parse_script(source: &str) -> Result<Script> {
             current_run = Some(Run {
                 args,
                 in_contexts: current_in.clone(),
-
                 diff_from: current_from.clone(),
+                stdin: None,
             });
         } else if let Some(rest) = line.strip_prefix("in ") {
             // Always block-level: flush current run, scope subsequent runs
@@ -266,21 +271,15 @@ pub fn parse_script(source: &str) -> Result<Script> {
             if let Some((_, ref mut flag_lists)) = current_combine {
                 flag_lists.push(flags);
             }
-        } else if line.starts_with("stdin ") {
-            // stdin is now a context property — attach to current context
-            let ctx = current_context.as_mut().ok_or_else(|| {
-                anyhow::anyhow!("line {}: 'stdin' outside of a context block", line_num)
-            })?;
-            let rest = line.strip_prefix("stdin ").unwrap().trim();
-            if let Some(path) = rest.strip_prefix("from ") {
-                let tokens = tokenize(path, line_num)?;
-                if tokens.is_empty() {
-                    bail!("line {}: stdin from requires a path", line_num);
-                }
-                ctx.stdin = Some(StdinSource::FromFile(tokens[0].clone()));
+        } else if let Some(rest) = line.strip_prefix("stdin ") {
+            // Indented under a run: run modifier. Otherwise a context property.
+            let source = parse_stdin(rest.trim(), line_num)?;
+            if let Some(run) = current_run.as_mut().filter(|_| is_indented) {
+                run.stdin = Some(source);
+            } else if let Some(ctx) = current_context.as_mut() {
+                ctx.stdin = Some(source);
             } else {
-                let lines = tokenize(rest, line_num)?;
-                ctx.stdin = Some(StdinSource::Lines(lines));
+                bail!("line {}: 'stdin' outside of a context block or run", line_num);
             }
         } else {
             // Setup command — goes into current context or vary block
@@ -309,6 +308,8 @@ pub fn parse_script(source: &str) -> Result<Script> {
         });
     }
 
+    validate_runs(&runs)?;
+
     resolve_extends(&mut contexts)?;
     resolve_vary(&mut contexts, &vary_blocks)?;
     resolve_stress(&mut contexts, &stress_blocks)?;
@@ -316,6 +317,72 @@ pub fn parse_script(source: &str) -> Result<Script> {
     Ok(Script { contexts, runs })
 }
 
+/// Upper bound on inline stdin content, so a probe file can't balloon
+/// every generated batch script.
+pub const MAX_STDIN_BYTES: usize = 64 * 1024;
+
+/// Upper bounds on one run's argv, after `combine` expansion. Generous for
+/// any real invocation, but a malformed probe fails here with a clear error
+/// instead of as E2BIG inside the sandbox.
+pub const MAX_RUN_ARGS: usize = 1024;
+pub const MAX_RUN_ARG_BYTES: usize = 128 * 1024;
+
+/// Checks every run list must pass before execution, whether parsed from a
+/// probe file or generated by explore mode.
+pub fn validate_runs(runs: &[Run]) -> Result<()> {
+    for run in runs {
+        check_run_size(run)?;
+    }
+    check_stdin_collisions(runs)
+}
+
+fn check_run_size(run: &Run) -> Result<()> {
+    let bytes: usize = run.args.iter()
+        .map(|a| match a { Arg::Literal(s) | Arg::Extract(s) => s.len() + 1 })
+        .sum();
+    if run.args.len() > MAX_RUN_ARGS || bytes > MAX_RUN_ARG_BYTES {
+        let label: String = run.args.first().map(|a| a.display()).unwrap_or_default();
+        bail!("run {} ...: {} arguments, {} bytes (max {} arguments, {} bytes)",
+            label, run.args.len(), bytes, MAX_RUN_ARGS, MAX_RUN_ARG_BYTES);
+    }
+    Ok(())
+}
+
+/// Runs are identified by their args (in reports, groups, and `from`
+/// references), so two runs with the same args must not differ in stdin:
+/// one would silently stand in for the other. Varying stdin is what
+/// contexts are for.
+fn check_stdin_collisions(runs: &[Run]) -> Result<()> {
+    let mut seen: HashMap<&[Arg], &Option<StdinSource>> = HashMap::new();
+    for run in runs {
+        if let Some(prev) = seen.insert(&run.args, &run.stdin) {
+            if *prev != run.stdin {
+                let label = run.args.iter().map(|a| a.display()).collect::<Vec<_>>().join(" ");
+                bail!("run {} appears twice with different stdin; \
+                    give each stdin its own context instead", label);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Parse the remainder of a `stdin` line: `from "file"` or inline lines.
+fn parse_stdin(rest: &str, line_num: usize) -> Result<StdinSource> {
+    if let Some(path) = rest.strip_prefix("from ") {
+        let tokens = tokenize(path, line_num)?;
+        if tokens.is_empty() {
+            bail!("line {}: stdin from requires a path", line_num);
+        }
+        return Ok(StdinSource::FromFile(tokens[0].clone()));
+    }
+    let lines = tokenize(rest, line_num)?;
+    let size: usize = lines.iter().map(|l| l.len() + 1).sum();
+    if size > MAX_STDIN_BYTES {
+        bail!("line {}: stdin content is {} bytes (max {})", line_num, size, MAX_STDIN_BYTES);
+    }
+    Ok(StdinSource::Lines(lines))
+}
+
 fn flush_run(run: &mut Option<Run>, runs: &mut Vec<Run>) {
     if let Some(r) = run.take() {
         runs.push(r);
@@ -343,8 +410,8 @@ fn flush_combine(
             runs.push(Run {
                 args,
                 in_contexts: current_in.clone(),
-
                 diff_from: current_from.clone(),
+                stdin: None,
             });
         }
 
@@ -358,8 +425,8 @@ fn flush_combine(
                 runs.push(Run {
                     args,
                     in_contexts: current_in.clone(),
-    
                     diff_from: current_from.clone(),
+                    stdin: None,
                 });
             }
         }
@@ -460,10 +527,28 @@ fn parse_setup_line(line: &str, line_num: usize) -> Result<SetupCommand> {
         "invoke" => {
             Ok(SetupCommand::Invoke { args: tokens[1..].to_vec() })
         }
+        "archive" => {
+            if tokens.len() < 2 { bail!("line {}: archive requires a path", line_num); }
+            let into = match tokens.get(2).map(|s| s.as_str()) {
+                None => ".".to_string(),
+                Some("into") if tokens.len() >= 4 => tokens[3].clone(),
+                _ => bail!("line {}: expected archive \"file\" [into \"dir\"]", line_num),
+            };
+            // tar runs on the host, so this must stay inside the workspace
+            if escapes_workspace(&into) {
+                bail!("line {}: archive into {:?} escapes the workspace", line_num, into);
+            }
+            Ok(SetupCommand::Archive { src: tokens[1].clone(), into })
+        }
         _ => bail!("line {}: unknown command '{}'", line_num, tokens[0]),
     }
 }
 
+/// True if a workspace-relative path is absolute or has a `..` component.
+pub fn escapes_workspace(path: &str) -> bool {
+    path.starts_with('/') || path.split('/').any(|c| c == "..")
+}
+
 fn resolve_extends(contexts: &mut [NamedContext]) -> Result<()> {
     // Store original (pre-resolution) commands per context.
     let own_cmds: HashMap<String, Vec<SetupCommand>> = contexts
@@ -580,6 +665,7 @@ pub fn describe_perturbation(cmd: &SetupCommand) -> String {
         }
         SetupCommand::SetEnv { var, value } => format!("env {}={}", var, value),
         SetupCommand::Invoke { args } => format!("run {:?}", args),
+        SetupCommand::Archive { src, .. } => format!("archive:{}", src),
         _ => format!("{:?}", cmd),
     }
 }
@@ -726,6 +812,22 @@ pub fn tokenize(line: &str, _line_num: usize) -> Result<Vec<String>> {
     Ok(tokens)
 }
 
+/// Quote a string as a probe-language token; the inverse of `tokenize`.
+pub fn quote(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
 
 /// Strip inline comments: everything after an unquoted `#` is removed.
 fn strip_comment(line: &str) -> &str {
@@ -914,6 +1016,95 @@ run "." "-1"
         assert!(script.runs[5].diff_from.is_none()); // run "." "-1" — unindented, clears from
     }
 
+    #[test]
+    fn test_run_stdin_overrides_context() {
+        let source = r#"
+context "base"
+  file "a.txt" "hello"
+  stdin "from context"
+
+run "error"
+  stdin "error: piped" "no match"
+run "error" "-"
+"#;
+        let script = parse_script(source).unwrap();
+        assert!(matches!(script.contexts[0].stdin, Some(StdinSource::Lines(_))));
+        match &script.runs[0].stdin {
+            Some(StdinSource::Lines(lines)) => assert_eq!(lines, &["error: piped", "no match"]),
+            other => panic!("expected run stdin, got {:?}", other),
+        }
+        assert!(script.runs[1].stdin.is_none());
+
+        let big = format!("context \"b\"\n\nrun \".\"\n  stdin \"{}\"\n", "x".repeat(MAX_STDIN_BYTES));
+        assert!(parse_script(&big).is_err());
+    }
+
+    #[test]
+    fn test_reject_runs_differing_only_in_stdin() {
+        let source = r#"
+context "base"
+
+run "-"
+  stdin "b" "a"
+run "-"
+  stdin "c" "d"
+"#;
+        let err = parse_script(source).unwrap_err().to_string();
+        assert!(err.contains("appears twice with different stdin"), "{}", err);
+
+        // Same args with the same stdin (e.g. scoped to different contexts) is fine
+        let scoped = r#"
+context "a"
+context "b"
+
+in "a"
+run "-"
+  stdin "x"
+in "b"
+run "-"
+  stdin "x"
+"#;
+        assert_eq!(parse_script(scoped).unwrap().runs.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_archive() {
+        let source = "context \"repo\"\n  archive \"snap.tar.gz\"\n  archive \"more.tar\" into \"vendor\"\n";
+        let script = parse_script(source).unwrap();
+        let cmds = &script.contexts[0].commands;
+        assert!(matches!(&cmds[0], SetupCommand::Archive { src, into } if src == "snap.tar.gz" && into == "."));
+        assert!(matches!(&cmds[1], SetupCommand::Archive { src, into } if src == "more.tar" && into == "vendor"));
+        assert!(parse_script("context \"r\"\n  archive \"a.tar\" to \"x\"\n").is_err());
+        for into in ["/home/me", "../up", "a/../../b"] {
+            let source = format!("context \"r\"\n  archive \"a.tar\" into \"{}\"\n", into);
+            let err = format!("{:#}", parse_script(&source).unwrap_err());
+            assert!(err.contains("escapes the workspace"), "{}", err);
+        }
+        assert!(parse_script("context \"r\"\n  archive \"a.tar\" into \"a/..b\"\n").is_ok());
+    }
+
+    #[test]
+    fn test_reject_oversized_run() {
+        let many = format!("run {}\n", vec!["\"a\""; MAX_RUN_ARGS + 1].join(" "));
+        let err = parse_script(&many).unwrap_err().to_string();
+        assert!(err.contains("1025 arguments"), "{}", err);
+
+        let long = format!("run \"{}\"\n", "x".repeat(MAX_RUN_ARG_BYTES));
+        assert!(parse_script(&long).is_err());
+
+        let ok = format!("run {}\n", vec!["\"a\""; MAX_RUN_ARGS].join(" "));
+        assert!(parse_script(&ok).is_ok());
+
+        // Runs built in code (explore mode) go through the same check
+        let generated = Run {
+            args: vec![Arg::from("--width"), Arg::from("x".repeat(MAX_RUN_ARG_BYTES).as_str())],
+            in_contexts: None,
+            diff_from: None,
+            stdin: None,
+        };
+        assert!(validate_runs(&[generated]).is_err());
+    }
+
     #[test]
     fn test_reject_expect() {
         let source = "context \"b\"\n  file \"a\" \"b\"\n\nrun \".\"\n  expect stdout not-empty\n";
@@ -6,6 +6,7 @@ use std::collections::{HashMap, HashSet};
 
 use crate::analyze::{AnalysisMetrics, RunAnalysis};
 use crate::discover::FlagInfo;
+use crate::parse::Arg;
 use crate::output;
 
 /// Extract the canonical flag stem from a run label.
@@ -57,6 +58,8 @@ pub fn format_run_report(
     probe_name: &str,
     cell_count: usize,
     setup_failures: &HashMap<String, String>,
+    peak_rss_kb: Option<u64>,
+    limits: output::PreviewLimits,
 ) -> String {
     let mut out = String::new();
 
@@ -65,6 +68,9 @@ pub fn format_run_report(
         "# Results for {}\n# {} contexts, {} runs, {} cells\n",
         probe_name, metrics.context_count, metrics.total_runs, cell_count
     ));
+    if let Some(kb) = peak_rss_kb {
+        out.push_str(&format!("# Peak RSS: {} KiB (largest process in any context)\n", kb));
+    }
 
     // Alias map
     if let Some(fi) = flag_info {
@@ -74,8 +80,10 @@ pub fn format_run_report(
         }
     }
 
-    // Setup failures
-    for (ctx, err) in setup_failures {
+    // Setup failures (sorted: HashMap order would make reruns diff)
+    let mut failures: Vec<(&String, &String)> = setup_failures.iter().collect();
+    failures.sort();
+    for (ctx, err) in failures {
         out.push_str(&format!("\n# SETUP FAILED {}: {}\n", ctx, err));
     }
 
@@ -112,6 +120,17 @@ pub fn format_run_report(
             }
         }
 
+        // CPU per run, summed over its contexts
+        let cpu: Vec<String> = group.run_indices.iter().zip(&group.run_labels)
+            .filter_map(|(ri, label)| {
+                let ms = metrics.runs.iter().find(|r| r.run_index == *ri)?.cpu_time_ms?;
+                Some(format!("{} {}ms", label, ms))
+            })
+            .collect();
+        if !cpu.is_empty() {
+            out.push_str(&format!("  cpu: {}\n", cpu.join(", ")));
+        }
+
         // Summary: universals + sensitivity
         let mut summary = group.universals.clone();
         if !group.sensitivity.is_empty() {
@@ -125,7 +144,7 @@ pub fn format_run_report(
         out.push_str(&format!("  {}:\n", output::format_context_group(
             &group.majority_contexts.iter().map(|s| s.as_str()).collect::<Vec<_>>(),
             metrics.context_count)));
-        output::format_obs(&mut out, &group.majority_obs, "    ");
+        output::format_obs(&mut out, &group.majority_obs, "    ", limits);
 
         // vs-diffs
         if !group.vs_diffs.is_empty() {
@@ -169,11 +188,29 @@ pub struct RoundSummary {
     pub strategies: Vec<String>,
 }
 
-/// Format the exploration report.
+/// Per-flag classification behind an exploration report, for
+/// machine-readable output alongside the prose.
+#[derive(Debug, Default)]
+pub struct ExplorationSummary {
+    pub observed: usize,
+    pub total_flags: usize,
+    pub verified: Vec<String>,
+    pub errored: Vec<String>,
+    pub combination: Vec<String>,
+    pub indistinguishable: Vec<String>,
+    pub untested: Vec<String>,
+}
+
+/// Format the exploration report, returning the text and its summary.
 ///
 /// `ever_isolated` is the set of run labels in singleton behavioral groups.
 /// The report deduplicates to unique flag stems and separates solo-flag isolation
 /// from combination-based evidence.
+///
+/// With `require_success`, only exit 0 counts as a working run: output
+/// deltas from runs that errored (or exited with the binary's other
+/// operational codes) are reported under "Recognized but errored".
+#[allow(clippy::too_many_arguments)]
 pub fn format_exploration_report(
     rounds: &[RoundSummary],
     final_metrics: &AnalysisMetrics,
@@ -182,7 +219,8 @@ pub fn format_exploration_report(
     binary_label: &str,
     all_runs: &[&RunAnalysis],
     contexts: &[crate::parse::NamedContext],
-) -> String {
+    require_success: bool,
+) -> (String, ExplorationSummary) {
     let mut out = String::new();
     let aliases = flag_info.map(|fi| &fi.aliases);
     // total_flags from --help (includes both short and long forms of aliases)
@@ -328,13 +366,16 @@ pub fn format_exploration_report(
     let operational_exit_codes: HashSet<i32> = {
         let mut codes = HashSet::new();
         codes.insert(0); // exit 0 is always operational
-        for run in &final_metrics.runs {
-            // Base runs: no flag args (all args are positional/extract)
-            let has_flag = run.args.iter().any(|a| a.is_flag());
-            if !has_flag {
-                for (_, obs) in &run.context_groups {
-                    if let Some(code) = obs.exit_code {
-                        codes.insert(code);
+        // Under --require-success only exit 0 counts
+        if !require_success {
+            for run in &final_metrics.runs {
+                // Base runs: no flag args (all args are positional/extract)
+                let has_flag = run.args.iter().any(|a| a.is_flag());
+                if !has_flag {
+                    for (_, obs) in &run.context_groups {
+                        if let Some(code) = obs.exit_code {
+                            codes.insert(code);
+                        }
                     }
                 }
             }
@@ -405,6 +446,20 @@ pub fn format_exploration_report(
     if untested > 0 {
         out.push_str(&format!("  {} untested\n", untested));
     }
+    if require_success {
+        out.push_str("  policy: require-success (only exit 0 runs count as observed)\n");
+    }
+    if let Some(fi) = flag_info {
+        // Informational: help text marks these as not (or no longer) stable
+        let mut marked: Vec<&String> = all_stems.iter()
+            .filter(|s| fi.stability.contains_key(s.as_str()))
+            .collect();
+        marked.sort();
+        if !marked.is_empty() {
+            out.push_str(&format!("  {} marked experimental or deprecated in --help: {}\n",
+                marked.len(), marked.iter().map(|s| s.as_str()).collect::<Vec<_>>().join(", ")));
+        }
+    }
 
     // Robustness summary: count flags by confidence tier
     if !final_metrics.robustness.is_empty() {
@@ -435,6 +490,8 @@ pub fn format_exploration_report(
     // exemplar actually shows — not by a heuristic over all runs.
     let mut verified_flags: Vec<(String, Exemplar)> = Vec::new();
     let mut errored_flags: Vec<(String, Option<Exemplar>)> = Vec::new();
+    // Flags whose exemplar changed output but errored, demoted by policy
+    let mut demoted: HashSet<String> = HashSet::new();
 
     let mut sorted_solo: Vec<&String> = solo_distinguished.iter().collect();
     sorted_solo.sort();
@@ -449,7 +506,10 @@ pub fn format_exploration_report(
             // Verified = produced observable output changes without erroring,
             // or has stdout diff evidence (which overrides error signals like
             // stderr warnings that accompany valid output)
-            if errored { has_output_diff } // errors: only if stdout also changed
+            if errored && require_success { // policy: a failing run never verifies
+                if has_output_diff { demoted.insert(flag.clone()); }
+                false
+            } else if errored { has_output_diff } // errors: only if stdout also changed
             else { e.vs_diff != "identical" || has_fs_effect } // no error: any change counts
         });
         if shows_working {
@@ -461,9 +521,7 @@ pub fn format_exploration_report(
 
     // Helper: render a flag with its exemplar
     let render_flag_with_ex = |out: &mut String, flag: &str, ex: &Exemplar| {
-        let desc = flag_info.and_then(|fi| fi.descs.get(flag))
-            .map(|d| format!("  # {}", first_sentence(d, 140)))
-            .unwrap_or_default();
+        let desc = flag_comment(flag_info, flag);
         out.push_str(&format!("  {}{}\n", flag, desc));
         out.push_str(&format!("    tested: {} in {}\n", ex.run_label, ex.context_name));
         for part in ex.vs_diff.split("; ") {
@@ -495,10 +553,11 @@ pub fn format_exploration_report(
     if !errored_flags.is_empty() {
         out.push_str("## Recognized but errored (flag accepted, no successful output observed)\n");
         for (flag, ex) in &errored_flags {
-            let desc = flag_info.and_then(|fi| fi.descs.get(flag.as_str()))
-                .map(|d| format!("  # {}", first_sentence(d, 140)))
-                .unwrap_or_default();
+            let desc = flag_comment(flag_info, flag);
             out.push_str(&format!("  {}{}\n", flag, desc));
+            if demoted.contains(flag) {
+                out.push_str("    output changed, but the run errored (require-success)\n");
+            }
             if let Some(ex) = ex {
                 out.push_str(&format!("    tested: {} in {}\n", ex.run_label, ex.context_name));
                 for part in ex.vs_diff.split("; ") {
@@ -515,9 +574,7 @@ pub fn format_exploration_report(
         let mut sorted_combo: Vec<&String> = combo_distinguished.iter().collect();
         sorted_combo.sort();
         for flag in &sorted_combo {
-            let desc = flag_info.and_then(|fi| fi.descs.get(flag.as_str()))
-                .map(|d| format!("  # {}", first_sentence(d, 140)))
-                .unwrap_or_default();
+            let desc = flag_comment(flag_info, flag);
             out.push_str(&format!("  {}{}\n", flag, desc));
             // Show the behavioral diff summary from the flag's runs
             if let Some(diff) = find_vs_diff_for_flag(flag, all_runs, aliases) {
@@ -553,6 +610,13 @@ pub fn format_exploration_report(
         out.push('\n');
     }
 
+    if let Some(fi) = flag_info {
+        out.push_str(&format_conflicts(fi, all_runs, aliases));
+        out.push_str(&format_unexercised_values(fi, all_runs, aliases));
+        out.push_str(&format_exit_statuses(fi, all_runs));
+        out.push_str(&format_help_refs(fi, binary_label));
+    }
+
     // Untested
     if !final_metrics.untested_flags.is_empty() {
         out.push_str(&format!("## Untested ({}/{})\n  {}\n",
@@ -560,6 +624,277 @@ pub fn format_exploration_report(
             final_metrics.untested_flags.join(", ")));
     }
 
+    let mut indistinguishable: Vec<String> = indistinguishable_groups.into_iter()
+        .flatten()
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    indistinguishable.sort();
+    let summary = ExplorationSummary {
+        observed: total_observed,
+        total_flags: unique_stem_count,
+        verified: verified_flags.into_iter().map(|(f, _)| f).collect(),
+        errored: errored_flags.into_iter().map(|(f, _)| f).collect(),
+        combination: {
+            let mut combo: Vec<String> = combo_distinguished.into_iter().collect();
+            combo.sort();
+            combo
+        },
+        indistinguishable,
+        untested: final_metrics.untested_flags.clone(),
+    };
+
+    (out, summary)
+}
+
+/// List help-documented mutual exclusions with what the combined run did,
+/// if the design combined the pair. Empty when help documents none.
+fn format_conflicts(
+    fi: &FlagInfo,
+    all_runs: &[&RunAnalysis],
+    aliases: Option<&HashMap<String, String>>,
+) -> String {
+    let mut pairs: Vec<(String, String)> = Vec::new();
+    for (flag, others) in &fi.conflicts {
+        for other in others {
+            let a = canonical_flag(flag, aliases);
+            let b = canonical_flag(other, aliases);
+            let pair = if a <= b { (a, b) } else { (b, a) };
+            if pair.0 != pair.1 && !pairs.contains(&pair) {
+                pairs.push(pair);
+            }
+        }
+    }
+    if pairs.is_empty() {
+        return String::new();
+    }
+    pairs.sort();
+
+    let mut out = String::from("## Documented conflicts (from --help)\n");
+    for (a, b) in &pairs {
+        let combined = all_runs.iter().find(|run| {
+            let keys: Vec<String> = run.args.iter()
+                .filter_map(|arg| arg.flag_key())
+                .map(|k| canonical_flag(k, aliases))
+                .collect();
+            keys.contains(a) && keys.contains(b)
+        });
+        let outcome = match combined {
+            Some(run) => {
+                let mut codes: Vec<i32> = run.context_groups.iter()
+                    .map(|(_, o)| o.exit_code.unwrap_or(-1))
+                    .collect();
+                codes.sort();
+                codes.dedup();
+                format!("combined run exits {}",
+                    codes.iter().map(|c| output::format_exit(*c)).collect::<Vec<_>>().join(", "))
+            }
+            None => "not combined in this design".to_string(),
+        };
+        out.push_str(&format!("  {} / {}: {}\n", a, b, outcome));
+    }
+    out.push('\n');
+    out
+}
+
+/// List help-documented values (e.g. `{always,never,auto}`) that no grid
+/// run passed to their flag: value probing rejected them, or another
+/// working value was chosen. Empty when every documented value ran.
+fn format_unexercised_values(
+    fi: &FlagInfo,
+    all_runs: &[&RunAnalysis],
+    aliases: Option<&HashMap<String, String>>,
+) -> String {
+    // Every (canonical flag, value) pair some run actually passed
+    let mut exercised: HashSet<(String, String)> = HashSet::new();
+    for run in all_runs {
+        let lits: Vec<&str> = run.args.iter()
+            .map(|a| match a { Arg::Literal(s) => s.as_str(), Arg::Extract(_) => "" })
+            .collect();
+        for (i, lit) in lits.iter().enumerate() {
+            if !lit.starts_with('-') { continue; }
+            if let Some((flag, value)) = lit.split_once('=') {
+                exercised.insert((canonical_flag(flag, aliases), value.to_string()));
+            } else if let Some(next) = lits.get(i + 1) {
+                exercised.insert((canonical_flag(lit, aliases), next.to_string()));
+            }
+        }
+    }
+
+    let mut missing: Vec<(String, Vec<&String>)> = Vec::new();
+    for (flag, values) in &fi.extracted_values {
+        let canon = canonical_flag(flag, aliases);
+        if missing.iter().any(|(f, _)| *f == canon) { continue; }
+        let unrun: Vec<&String> = values.iter()
+            .filter(|v| !exercised.contains(&(canon.clone(), v.to_string())))
+            .collect();
+        if !unrun.is_empty() {
+            missing.push((canon, unrun));
+        }
+    }
+    if missing.is_empty() {
+        return String::new();
+    }
+    missing.sort();
+
+    let mut out = String::from("## Documented values never run\n");
+    for (flag, values) in &missing {
+        out.push_str(&format!("  {}: {}\n", flag,
+            values.iter().map(|v| v.as_str()).collect::<Vec<_>>().join(", ")));
+    }
+    out.push('\n');
+    out
+}
+
+/// Check each exit status documented in help against the grid: how many
+/// runs produced it in some context. A documented code no run produced is
+/// an unproven claim (or a case the design never provoked).
+fn format_exit_statuses(fi: &FlagInfo, all_runs: &[&RunAnalysis]) -> String {
+    if fi.exit_statuses.is_empty() {
+        return String::new();
+    }
+    let mut out = String::from("## Documented exit statuses (from --help)\n");
+    for (code, meaning) in &fi.exit_statuses {
+        let runs = all_runs.iter()
+            .filter(|run| run.context_groups.iter().any(|(_, o)| o.exit_code == Some(*code)))
+            .count();
+        let observed = if runs == 0 {
+            "never observed".to_string()
+        } else {
+            format!("observed in {} run{}", runs, if runs == 1 { "" } else { "s" })
+        };
+        if meaning.is_empty() {
+            out.push_str(&format!("  {}: {}\n", code, observed));
+        } else {
+            out.push_str(&format!("  {} ({}): {}\n", code, meaning, observed));
+        }
+    }
+    let undocumented: Vec<i32> = {
+        let mut codes: Vec<i32> = all_runs.iter()
+            .flat_map(|run| run.context_groups.iter().filter_map(|(_, o)| o.exit_code))
+            // 124 is timeout(1)'s; above 125 are exec failures and signals
+            .filter(|c| *c != 124 && *c <= 125)
+            .filter(|c| !fi.exit_statuses.iter().any(|(d, _)| d == c))
+            .collect();
+        codes.sort();
+        codes.dedup();
+        codes
+    };
+    if !undocumented.is_empty() {
+        out.push_str(&format!("  also observed, undocumented: {}\n",
+            undocumented.iter().map(|c| c.to_string()).collect::<Vec<_>>().join(", ")));
+    }
+    out.push('\n');
+    out
+}
+
+/// Subcommands the help text refers to for more help. bgrid explores one
+/// command prefix per run, so these are listed as follow-up explorations.
+fn format_help_refs(fi: &FlagInfo, binary_label: &str) -> String {
+    let mut words = binary_label.split_whitespace();
+    let binary = words.next().unwrap_or(binary_label);
+    let explored: Vec<&str> = words.collect();
+    let refs: Vec<&String> = fi.help_refs.iter()
+        .filter(|r| !explored.contains(&r.as_str()))
+        .collect();
+    if refs.is_empty() {
+        return String::new();
+    }
+    let mut out = String::from("## Referenced subcommands (from --help)\n");
+    for sub in refs {
+        out.push_str(&format!("  {}: bgrid {} {}\n", sub, binary, sub));
+    }
+    out.push('\n');
+    out
+}
+
+/// Strip the insignificant whitespace from JSON rendered by this module,
+/// leaving strings and structure untouched. Keeps the trailing newline.
+pub fn compact_json(json: &str) -> String {
+    let mut out = String::with_capacity(json.len());
+    let (mut in_string, mut escaped) = (false, false);
+    for c in json.chars() {
+        if in_string {
+            out.push(c);
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+        } else if c == '"' {
+            in_string = true;
+            out.push(c);
+        } else if !c.is_whitespace() {
+            out.push(c);
+        }
+    }
+    out.push('\n');
+    out
+}
+
+/// Render an exploration summary as JSON. Hand-written to keep the
+/// dependency set small; bump `schema_version` on incompatible changes.
+pub fn format_summary_json(
+    binary_label: &str,
+    binary: &str,
+    cell_count: usize,
+    peak_rss_kb: Option<u64>,
+    metrics: &AnalysisMetrics,
+    summary: &ExplorationSummary,
+) -> String {
+    let (path, hash) = binary_fingerprint(binary);
+    let list = |flags: &[String]| -> String {
+        format!("[{}]", flags.iter().map(|f| json_string(f)).collect::<Vec<_>>().join(", "))
+    };
+    let mut out = String::from("{\n");
+    out.push_str("  \"schema_version\": 1,\n");
+    out.push_str(&format!("  \"command\": {},\n", json_string(binary_label)));
+    out.push_str(&format!("  \"binary\": {},\n", json_string(&path.to_string_lossy())));
+    out.push_str(&format!("  \"binary_fnv1a\": {},\n", json_string(&hash)));
+    out.push_str(&format!("  \"contexts\": {},\n", metrics.context_count));
+    out.push_str(&format!("  \"runs\": {},\n", metrics.total_runs));
+    out.push_str(&format!("  \"cells\": {},\n", cell_count));
+    out.push_str(&format!("  \"observed\": {},\n", summary.observed));
+    out.push_str(&format!("  \"total_flags\": {},\n", summary.total_flags));
+    out.push_str("  \"flags\": {\n");
+    out.push_str(&format!("    \"verified\": {},\n", list(&summary.verified)));
+    out.push_str(&format!("    \"errored\": {},\n", list(&summary.errored)));
+    out.push_str(&format!("    \"combination\": {},\n", list(&summary.combination)));
+    out.push_str(&format!("    \"indistinguishable\": {},\n", list(&summary.indistinguishable)));
+    out.push_str(&format!("    \"untested\": {}\n", list(&summary.untested)));
+    out.push_str("  },\n");
+    out.push_str("  \"resources\": {\n");
+    out.push_str(&format!("    \"peak_rss_kb\": {},\n",
+        peak_rss_kb.map(|kb| kb.to_string()).unwrap_or_else(|| "null".into())));
+    let cpu: Vec<String> = metrics.runs.iter()
+        .filter_map(|r| Some(format!("      {}: {}", json_string(&r.args_str), r.cpu_time_ms?)))
+        .collect();
+    if cpu.is_empty() {
+        out.push_str("    \"cpu_time_ms\": {}\n");
+    } else {
+        out.push_str(&format!("    \"cpu_time_ms\": {{\n{}\n    }}\n", cpu.join(",\n")));
+    }
+    out.push_str("  }\n}\n");
+    out
+}
+
+/// Quote and escape a string as a JSON string literal.
+pub fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
     out
 }
 
@@ -700,10 +1035,18 @@ fn find_exemplar(
     // Build preview showing the first region where base and flag DIFFER.
     // Skip shared prefix lines so the reader sees the actual change.
     // Strip ANSI escapes so output is readable.
-    let (base_preview, flag_preview) = if let Some(base) = base_obs {
-        diff_preview(&output::strip_ansi(&base.stdout), &output::strip_ansi(&flag_obs.stdout), 6)
-    } else {
-        (String::new(), truncate_lines(&output::strip_ansi(&flag_obs.stdout), 6))
+    // Binary stdout is shown as a placeholder rather than escape noise.
+    let (base_preview, flag_preview) = match (base_obs, flag_obs.binary_stdout) {
+        (Some(base), None) if base.binary_stdout.is_none() =>
+            diff_preview(&output::strip_ansi(&base.stdout), &output::strip_ansi(&flag_obs.stdout), 6),
+        (Some(base), flag_bin) => (
+            base.binary_stdout.map(output::binary_placeholder)
+                .unwrap_or_else(|| truncate_lines(&output::strip_ansi(&base.stdout), 6)),
+            flag_bin.map(output::binary_placeholder)
+                .unwrap_or_else(|| truncate_lines(&output::strip_ansi(&flag_obs.stdout), 6)),
+        ),
+        (None, Some(n)) => (String::new(), output::binary_placeholder(n)),
+        (None, None) => (String::new(), truncate_lines(&output::strip_ansi(&flag_obs.stdout), 6)),
     };
 
     Some(Exemplar {
@@ -720,13 +1063,19 @@ fn find_exemplar(
 fn describe_observation(obs: &crate::execute::Observation) -> String {
     let mut parts = Vec::new();
     let stdout_lines = obs.stdout.lines().count();
-    if stdout_lines > 0 {
+    if let Some(n) = obs.binary_stdout {
+        parts.push(format!("stdout: {}", output::binary_placeholder(n)));
+    } else if stdout_lines > 0 {
         parts.push(format!("stdout: {} lines", stdout_lines));
     }
     parts.push(format!("exit {}", output::format_exit(obs.exit_code.unwrap_or(-1))));
     if !obs.stderr.trim().is_empty() {
-        let first = obs.stderr.lines().next().unwrap_or("").trim();
-        parts.push(format!("stderr: {}", first));
+        let lines: Vec<&str> = obs.stderr.trim().lines().collect();
+        if lines.len() > 1 {
+            parts.push(format!("stderr: {} {}", lines[0].trim(), output::truncation_marker("stderr", &lines[1..])));
+        } else {
+            parts.push(format!("stderr: {}", lines[0].trim()));
+        }
     }
     for c in &obs.fs_changes {
         match c {
@@ -760,7 +1109,7 @@ fn diff_preview(base: &str, flag: &str, max_lines: usize) -> (String, String) {
         let region: Vec<&str> = lines[start..end].to_vec();
         let mut result = region.join("\n");
         if end < lines.len() {
-            result.push_str(&format!("\n      ... ({} more)", lines.len() - end));
+            result.push_str(&format!("\n      {}", output::truncation_marker("stdout", &lines[end..])));
         }
         if start > 0 {
             result = format!("      ... ({} identical)\n{}", start, result);
@@ -771,13 +1120,13 @@ fn diff_preview(base: &str, flag: &str, max_lines: usize) -> (String, String) {
     (format_region(&base_lines, start, max_lines), format_region(&flag_lines, start, max_lines))
 }
 
-/// Truncate to first N lines with "... (N more)" suffix.
+/// Truncate stdout to first N lines with a truncation marker suffix.
 fn truncate_lines(s: &str, max: usize) -> String {
     let lines: Vec<&str> = s.lines().collect();
     if lines.len() <= max {
         lines.join("\n")
     } else {
-        format!("{}\n      ... ({} more)", lines[..max].join("\n"), lines.len() - max)
+        format!("{}\n      {}", lines[..max].join("\n"), output::truncation_marker("stdout", &lines[max..]))
     }
 }
 
@@ -826,6 +1175,61 @@ fn first_sentence(desc: &str, max_len: usize) -> &str {
     desc[..end].trim_end()
 }
 
+/// Footer recording which bgrid build observed which binary, and when.
+/// The binary hash is FNV-1a over the resolved executable, which is
+/// enough to tell two builds apart without pulling in a crypto crate.
+pub fn format_provenance(binary: &str) -> String {
+    let (path, hash) = binary_fingerprint(binary);
+    let secs = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    format!(
+        "\n# Generated by bgrid {} for {} (fnv1a {}) on {}\n",
+        env!("CARGO_PKG_VERSION"), path.display(), hash, utc_date(secs),
+    )
+}
+
+/// Resolved path of `binary` and a short FNV-1a hash of its contents.
+fn binary_fingerprint(binary: &str) -> (std::path::PathBuf, String) {
+    let path = which::which(binary).unwrap_or_else(|_| std::path::PathBuf::from(binary));
+    let hash = std::fs::read(&path)
+        .map(|bytes| {
+            let h = bytes.iter().fold(0xcbf29ce484222325u64, |h, &b| {
+                (h ^ b as u64).wrapping_mul(0x100000001b3)
+            });
+            format!("{:016x}", h)[..12].to_string()
+        })
+        .unwrap_or_else(|_| "unreadable".into());
+    (path, hash)
+}
+
+/// Format seconds since the Unix epoch as a UTC `YYYY-MM-DD` date.
+fn utc_date(secs: u64) -> String {
+    // Civil-from-days (H. Hinnant), valid for all post-1970 dates.
+    let z = secs / 86400 + 719468;
+    let era = z / 146097;
+    let doe = z % 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Trailing `# description` comment for a flag, prefixed with its
+/// stability marker (e.g. `[deprecated]`) when the help text has one.
+fn flag_comment(flag_info: Option<&FlagInfo>, flag: &str) -> String {
+    let Some(fi) = flag_info else { return String::new() };
+    let Some(desc) = fi.descs.get(flag) else { return String::new() };
+    let marker = fi.stability.get(flag)
+        .map(|s| format!("[{}] ", s.label()))
+        .unwrap_or_default();
+    format!("  # {}{}", marker, first_sentence(desc, 140))
+}
+
 fn format_alias_map(aliases: &HashMap<String, String>) -> String {
     let mut pairs: Vec<String> = Vec::new();
     let mut seen = std::collections::HashSet::new();
@@ -839,3 +1243,193 @@ fn format_alias_map(aliases: &HashMap<String, String>) -> String {
     pairs.sort();
     pairs.join(", ")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_string_escapes() {
+        assert_eq!(json_string("--sort"), "\"--sort\"");
+        assert_eq!(json_string("a\"b\\c\n\x01"), "\"a\\\"b\\\\c\\n\\u0001\"");
+    }
+
+    #[test]
+    fn test_compact_json() {
+        let pretty = "{\n  \"a b\": [1, 2],\n  \"q\": \"x \\\" y\",\n  \"n\": null\n}\n";
+        assert_eq!(compact_json(pretty), "{\"a b\":[1,2],\"q\":\"x \\\" y\",\"n\":null}\n");
+    }
+
+    #[test]
+    fn test_utc_date() {
+        assert_eq!(utc_date(0), "1970-01-01");
+        assert_eq!(utc_date(951782400), "2000-02-29");
+        assert_eq!(utc_date(1735689599), "2024-12-31");
+    }
+
+    #[test]
+    fn test_truncation_markers() {
+        assert_eq!(truncate_lines("a\nb", 2), "a\nb");
+        assert_eq!(truncate_lines("a\nb\ncc\nddd", 2),
+            "a\nb\n      ... [stdout truncated: 2 more lines, 7 bytes]");
+        let (base, flag) = diff_preview("a\nb\nc\ny", "a\nb\nc\nz\nw", 2);
+        assert_eq!(base, "      ... (2 identical)\nc\ny");
+        assert_eq!(flag, "      ... (2 identical)\nc\nz\n      ... [stdout truncated: 1 more line, 2 bytes]");
+    }
+
+    #[test]
+    fn test_format_obs_limits() {
+        let obs = crate::execute::Observation {
+            stdout: (1..=25).map(|i| format!("{}\n", i)).collect(),
+            binary_stdout: None,
+            stderr: "e1\ne2\ne3\ne4\ne5\ne6\n".into(),
+            exit_code: Some(1),
+            fs_changes: vec![],
+            resources: crate::execute::ResourceUsage::default(),
+        };
+        let render = |limits| {
+            let mut out = String::new();
+            output::format_obs(&mut out, &obs, "", limits);
+            out
+        };
+
+        // Default: stdout capped at 20 lines, stderr kept whole
+        let text = render(output::PreviewLimits::default());
+        assert!(text.contains("  20\n  ... [stdout truncated: 5 more lines, 15 bytes]\n"), "{}", text);
+        assert!(text.contains("stderr: e1\n  e2\n  e3\n  e4\n  e5\n  e6\nexit: 1\n"), "{}", text);
+
+        let text = render(output::PreviewLimits { stdout_lines: None, stderr_lines: Some(2) });
+        assert!(text.contains("  25\nstderr: e1\n  e2\n  ... [stderr truncated: 4 more lines, 12 bytes]\n"), "{}", text);
+        assert!(!text.contains("stdout truncated"), "{}", text);
+    }
+
+    #[test]
+    fn test_run_report_setup_failures_sorted() {
+        let metrics = AnalysisMetrics {
+            groups: vec![],
+            runs: vec![],
+            untested_flags: vec![],
+            context_count: 3,
+            total_runs: 0,
+            robustness: HashMap::new(),
+        };
+        let failures = |order: &[&str]| -> HashMap<String, String> {
+            order.iter().map(|c| (c.to_string(), format!("{} broke", c))).collect()
+        };
+        let first = format_run_report(&metrics, None, "t.probe", 0,
+            &failures(&["zeta", "alpha", "mid"]), None, output::PreviewLimits::default());
+        let second = format_run_report(&metrics, None, "t.probe", 0,
+            &failures(&["mid", "zeta", "alpha"]), None, output::PreviewLimits::default());
+        assert_eq!(first, second);
+        let expected = "\n# SETUP FAILED alpha: alpha broke\n\
+                        \n# SETUP FAILED mid: mid broke\n\
+                        \n# SETUP FAILED zeta: zeta broke\n";
+        assert!(first.contains(expected), "{}", first);
+    }
+
+    #[test]
+    fn test_exit_statuses_against_grid() {
+        let fi = crate::discover::extract_flag_info("\
+Exit status:
+ 0  if OK,
+ 2  if serious trouble.
+");
+        let obs = |code: i32| crate::execute::Observation {
+            stdout: String::new(),
+            binary_stdout: None,
+            stderr: String::new(),
+            exit_code: Some(code),
+            fs_changes: vec![],
+            resources: crate::execute::ResourceUsage { wall_time_ms: 0, cpu_time_ms: None },
+        };
+        let run = |codes: &[i32]| RunAnalysis {
+            run_index: 0,
+            args: vec![],
+            args_str: String::new(),
+            majority_obs: obs(codes[0]),
+            majority_contexts: vec![],
+            context_groups: codes.iter().map(|c| (vec![], obs(*c))).collect(),
+            sensitivity: vec![],
+            universals: vec![],
+            from_ref: None,
+            vs_diff: None,
+            has_anomaly: false,
+            obs_count: codes.len(),
+            cpu_time_ms: None,
+        };
+        let runs = [run(&[0, 1]), run(&[0]), run(&[124])];
+        let refs: Vec<&RunAnalysis> = runs.iter().collect();
+        assert_eq!(format_exit_statuses(&fi, &refs), "\
+## Documented exit statuses (from --help)
+  0 (if OK): observed in 2 runs
+  2 (if serious trouble): never observed
+  also observed, undocumented: 1
+
+");
+    }
+
+    #[test]
+    fn test_resources_reported() {
+        let obs = crate::execute::Observation {
+            stdout: String::new(),
+            binary_stdout: None,
+            stderr: String::new(),
+            exit_code: Some(0),
+            fs_changes: vec![],
+            resources: crate::execute::ResourceUsage::default(),
+        };
+        let run = |args_str: &str, cpu_time_ms: Option<u64>| RunAnalysis {
+            run_index: 0,
+            args: vec![],
+            args_str: args_str.to_string(),
+            majority_obs: obs.clone(),
+            majority_contexts: vec![],
+            context_groups: vec![],
+            sensitivity: vec![],
+            universals: vec![],
+            from_ref: None,
+            vs_diff: None,
+            has_anomaly: false,
+            obs_count: 1,
+            cpu_time_ms,
+        };
+        let metrics = AnalysisMetrics {
+            groups: vec![],
+            runs: vec![run("\"-r\"", Some(12)), run("\"-n\"", None)],
+            untested_flags: vec![],
+            context_count: 1,
+            total_runs: 2,
+            robustness: HashMap::new(),
+        };
+        let json = format_summary_json("sh", "sh", 2, Some(2048), &metrics, &ExplorationSummary::default());
+        assert!(json.contains("  \"resources\": {\n    \"peak_rss_kb\": 2048,\n    \"cpu_time_ms\": {\n      \"\\\"-r\\\"\": 12\n    }\n  }\n}\n"), "{}", json);
+        let json = format_summary_json("sh", "sh", 2, None, &metrics, &ExplorationSummary::default());
+        assert!(json.contains("\"peak_rss_kb\": null,"), "{}", json);
+
+        let text = format_run_report(&metrics, None, "t.probe", 2, &HashMap::new(), Some(2048),
+            output::PreviewLimits::default());
+        assert!(text.contains("# Peak RSS: 2048 KiB (largest process in any context)\n"), "{}", text);
+        let text = format_run_report(&metrics, None, "t.probe", 2, &HashMap::new(), None,
+            output::PreviewLimits::default());
+        assert!(!text.contains("Peak RSS"), "{}", text);
+    }
+
+    #[test]
+    fn test_help_refs_as_follow_ups() {
+        let help = "See 'git help diff' and 'git help config' for details.\n";
+        let mut fi = crate::discover::extract_flag_info(help);
+        fi.help_refs = crate::discover::mine_help_refs(help, "git");
+        assert_eq!(format_help_refs(&fi, "git"), "\
+## Referenced subcommands (from --help)
+  diff: bgrid git diff
+  config: bgrid git config
+
+");
+        // The subcommand being explored is not its own follow-up
+        assert_eq!(format_help_refs(&fi, "git diff"), "\
+## Referenced subcommands (from --help)
+  config: bgrid git config
+
+");
+    }
+}
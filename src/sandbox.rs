@@ -1,28 +1,117 @@
 //! Sandbox construction and execution via bubblewrap.
 
-use crate::parse::{FileContent, Property, SetupCommand};
+use crate::parse::{escapes_workspace, FileContent, Property, SetupCommand};
 use anyhow::{Context, Result};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
+use std::sync::Mutex;
 
 /// Path to the bwrap binary. Found once at startup.
 pub struct Sandbox {
     bwrap: PathBuf,
+    verbose: bool,
+    /// Identity inside the sandbox; None keeps the caller's uid/gid.
+    uid: Option<u32>,
+    gid: Option<u32>,
+    /// Wall-clock limit for each invocation of the binary, via timeout(1).
+    cell_timeout_ms: u64,
 }
 
 impl Sandbox {
     /// Find bwrap or fail with a clear error.
     pub fn new() -> Result<Self> {
-        let bwrap = which::which("bwrap")
-            .context("bwrap not found — install bubblewrap for sandbox isolation")?;
-        Ok(Sandbox { bwrap })
+        Ok(Self::at(find_bwrap()?))
+    }
+
+    /// Default settings around a given bwrap path, without checking it.
+    pub fn at(bwrap: PathBuf) -> Self {
+        Sandbox {
+            bwrap,
+            verbose: false,
+            uid: None,
+            gid: None,
+            cell_timeout_ms: crate::execute::CELL_TIMEOUT_SECS * 1000,
+        }
+    }
+
+    /// Print every constructed bwrap command line to stderr.
+    pub fn with_verbose(mut self, verbose: bool) -> Self {
+        self.verbose = verbose;
+        self
+    }
+
+    /// Run sandboxed processes as the given uid/gid via a user namespace,
+    /// e.g. to observe an unprivileged user's behavior when bgrid runs as
+    /// root. The caller's real ids are mapped to these inside the sandbox.
+    pub fn with_identity(mut self, uid: Option<u32>, gid: Option<u32>) -> Result<Self> {
+        // (uid_t)-1 means "unchanged" to setuid(2) and can't be mapped
+        if uid == Some(u32::MAX) || gid == Some(u32::MAX) {
+            anyhow::bail!("uid/gid {} is not a mappable id", u32::MAX);
+        }
+        self.uid = uid;
+        self.gid = gid;
+        Ok(self)
+    }
+
+    /// Override the per-invocation timeout (default CELL_TIMEOUT_SECS).
+    pub fn with_cell_timeout_ms(mut self, ms: Option<u64>) -> Result<Self> {
+        if let Some(ms) = ms {
+            if ms == 0 {
+                anyhow::bail!("timeout must be a positive number of milliseconds");
+            }
+            self.cell_timeout_ms = ms;
+        }
+        Ok(self)
+    }
+
+    /// Whether command lines are traced to stderr.
+    pub fn verbose(&self) -> bool {
+        self.verbose
+    }
+
+    /// Per-invocation wall-clock limit in milliseconds.
+    pub fn cell_timeout_ms(&self) -> u64 {
+        self.cell_timeout_ms
+    }
+
+    /// Kill deadline for a batch of `cells` invocations sharing one bwrap:
+    /// every cell's limit plus one, saturating for huge --timeout-ms values.
+    pub fn batch_timeout_ms(&self, cells: usize) -> u64 {
+        self.cell_timeout_ms.saturating_mul((cells as u64).saturating_add(1))
+    }
+
+    /// The per-invocation limit as a timeout(1) duration, e.g. `2` or `0.250`.
+    pub fn timeout_arg(&self) -> String {
+        let (secs, ms) = (self.cell_timeout_ms / 1000, self.cell_timeout_ms % 1000);
+        if ms == 0 { secs.to_string() } else { format!("{}.{:03}", secs, ms) }
+    }
+
+    /// Resolved bwrap binary.
+    pub fn bwrap_path(&self) -> &Path {
+        &self.bwrap
+    }
+
+    /// Effective uid inside the sandbox.
+    pub fn uid(&self) -> u32 {
+        // SAFETY: getuid has no preconditions and cannot fail
+        self.uid.unwrap_or_else(|| unsafe { libc::getuid() })
+    }
+
+    /// Effective gid inside the sandbox.
+    pub fn gid(&self) -> u32 {
+        // SAFETY: getgid has no preconditions and cannot fail
+        self.gid.unwrap_or_else(|| unsafe { libc::getgid() })
+    }
+
+    /// Effective uid/gid inside the sandbox, for run headers.
+    pub fn identity(&self) -> String {
+        format!("uid={} gid={}", self.uid(), self.gid())
     }
 
     /// Build a Command that runs `binary args...` inside the bwrap sandbox.
     /// The workspace is bind-mounted read-write at /workspace.
-    /// If trace_dir is provided, it's bind-mounted at /trace for strace output.
     pub fn command(
         &self,
         binary: &str,
@@ -36,7 +125,7 @@ impl Sandbox {
         for arg in args {
             cmd.arg(arg);
         }
-
+        self.trace(&cmd);
         cmd
     }
 
@@ -53,10 +142,59 @@ impl Sandbox {
         cmd
     }
 
+    /// In verbose mode, echo the exact argv so the sandbox can be
+    /// reproduced by hand when it behaves differently from a shell.
+    fn trace(&self, cmd: &Command) {
+        if self.verbose {
+            eprintln!("  bwrap: {}", command_line(cmd));
+        }
+    }
+
+    /// In verbose mode, echo the standalone equivalent of one cell of a
+    /// batch script: the cell directory bound at /workspace, its env via
+    /// --setenv, and `timeout binary args`. `args` are shell words as the
+    /// batch script has them; `stdin` is a shell prefix that feeds the
+    /// bwrap process (e.g. `cat file | `), or empty.
+    pub fn trace_cell(
+        &self,
+        cell_dir: &Path,
+        env_vars: &HashMap<String, String>,
+        binary: &str,
+        args: &str,
+        stdin: &str,
+    ) {
+        if self.verbose {
+            eprintln!("  bwrap: {}", self.cell_command_line(cell_dir, env_vars, binary, args, stdin));
+        }
+    }
+
+    fn cell_command_line(
+        &self,
+        cell_dir: &Path,
+        env_vars: &HashMap<String, String>,
+        binary: &str,
+        args: &str,
+        stdin: &str,
+    ) -> String {
+        let mut cmd = self.bwrap_base(cell_dir, "/workspace", env_vars);
+        cmd.arg("--").arg("timeout").arg(self.timeout_arg()).arg(binary);
+        let args = if args.is_empty() { String::new() } else { format!(" {}", args) };
+        format!("{}{}{}", stdin, command_line(&cmd), args)
+    }
+
     /// Common bwrap setup: namespace isolation, system paths, workspace bind, env.
     fn bwrap_base(&self, work_dir: &Path, mount_point: &str, env_vars: &HashMap<String, String>) -> Command {
         let mut cmd = Command::new(&self.bwrap);
         cmd.arg("--unshare-net").arg("--die-with-parent");
+        if self.uid.is_some() || self.gid.is_some() {
+            cmd.arg("--unshare-user");
+            if let Some(uid) = self.uid {
+                cmd.arg("--uid").arg(uid.to_string());
+            }
+            if let Some(gid) = self.gid {
+                cmd.arg("--gid").arg(gid.to_string());
+            }
+        }
         for path in &["/nix", "/usr", "/bin", "/lib", "/lib64", "/etc", "/run"] {
             if Path::new(path).exists() {
                 cmd.arg("--ro-bind").arg(path).arg(path);
@@ -71,22 +209,31 @@ impl Sandbox {
         cmd.arg("--setenv").arg("PATH").arg(std::env::var("PATH").unwrap_or_default());
         cmd.arg("--setenv").arg("LANG").arg("C");
         cmd.arg("--setenv").arg("LC_ALL").arg("C");
-        for (k, v) in env_vars {
+        let mut vars: Vec<(&String, &String)> = env_vars.iter().collect();
+        vars.sort();
+        for (k, v) in vars {
             cmd.arg("--setenv").arg(k).arg(v);
         }
         cmd
     }
 }
 
+/// Locate bwrap on PATH.
+pub fn find_bwrap() -> Result<PathBuf> {
+    which::which("bwrap").context("bwrap not found — install bubblewrap for sandbox isolation")
+}
 
 /// Build sandbox state from setup commands.
 /// Returns accumulated env vars for use by run invocations.
+/// `checked_archives` holds archives already checked by check_archive, so
+/// later cells only extract them; it is shared by the grid's threads.
 pub fn apply_setup(
     work_dir: &Path,
     binary: &str,
     commands: &[SetupCommand],
     probe_dir: &Path,
     sandbox: &Sandbox,
+    checked_archives: &Mutex<HashSet<PathBuf>>,
 ) -> Result<HashMap<String, String>> {
     let mut env_vars: HashMap<String, String> = HashMap::new();
 
@@ -204,6 +351,15 @@ pub fn apply_setup(
             SetupCommand::RemoveEnv { var } => {
                 env_vars.remove(var.as_str());
             }
+            SetupCommand::Archive { src, into } => {
+                let resolved = if Path::new(src).is_absolute() {
+                    PathBuf::from(src)
+                } else {
+                    probe_dir.join(src)
+                };
+                unpack_archive(&resolved, work_dir, into, checked_archives)
+                    .with_context(|| format!("archive {}", src))?;
+            }
             SetupCommand::Invoke { args } => {
                 let str_args: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
                 let mut invoke = sandbox.command(binary, &str_args, work_dir, &env_vars);
@@ -230,6 +386,134 @@ pub fn apply_setup(
     Ok(env_vars)
 }
 
+/// Limits on an `archive` setup command, checked before anything is
+/// extracted, so a tarbomb fails setup instead of filling
+/// every cell directory.
+pub const MAX_ARCHIVE_ENTRIES: usize = 10_000;
+pub const MAX_ARCHIVE_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Unpack an archive into `into` under the workspace, checking it first
+/// unless it is already in `checked`.
+fn unpack_archive(archive: &Path, work_dir: &Path, into: &str, checked: &Mutex<HashSet<PathBuf>>) -> Result<()> {
+    // An earlier setup command may have made part of `into` a link
+    if let Some(link) = Path::new(into).ancestors()
+        .find(|p| !p.as_os_str().is_empty() && work_dir.join(p).is_symlink())
+    {
+        anyhow::bail!("into {:?} passes through the link {:?}", into, link);
+    }
+    // Not held while checking; two threads may both check, which is harmless
+    if !checked.lock().unwrap().contains(archive) {
+        check_archive(archive)?;
+        checked.lock().unwrap().insert(archive.to_path_buf());
+    }
+    extract_archive(archive, &work_dir.join(into))
+}
+
+/// Check a tar archive before anything is extracted from it: entry count,
+/// no absolute or `..` names or link targets, and content size.
+fn check_archive(archive: &Path) -> Result<()> {
+    let names = tar_listing(archive, "-tf")?;
+    let entries = names.lines().count();
+    if entries > MAX_ARCHIVE_ENTRIES {
+        anyhow::bail!("{} entries (max {})", entries, MAX_ARCHIVE_ENTRIES);
+    }
+    if let Some(bad) = names.lines().find(|n| escapes_workspace(n)) {
+        anyhow::bail!("entry {:?} escapes the workspace", bad);
+    }
+    // Link targets only show in the verbose listing, after the name
+    let verbose = tar_listing(archive, "-tvf")?;
+    if verbose.lines().count() != entries {
+        anyhow::bail!("tar listings disagree; is a name split across lines?");
+    }
+    for (name, line) in names.lines().zip(verbose.lines()) {
+        if let Some(target) = link_target(line, name).filter(|t| escapes_workspace(t)) {
+            anyhow::bail!("link {:?} -> {:?} escapes the workspace", name, target);
+        }
+    }
+    check_archive_bytes(archive, MAX_ARCHIVE_BYTES)
+}
+
+/// The target of a symlink (`name -> target`) or hardlink (`name link to
+/// target`) in a `tar -tv` line; None for other entries.
+fn link_target<'a>(line: &'a str, name: &str) -> Option<&'a str> {
+    [" -> ", " link to "].iter()
+        .find_map(|sep| line.split_once(&format!(" {}{}", name, sep)).map(|(_, t)| t))
+}
+
+fn tar_listing(archive: &Path, mode: &str) -> Result<String> {
+    let out = Command::new("tar")
+        .arg(mode).arg(archive)
+        .stdin(Stdio::null())
+        .output()
+        .context("run tar")?;
+    if !out.status.success() {
+        anyhow::bail!("tar could not list it: {}", String::from_utf8_lossy(&out.stderr).trim());
+    }
+    Ok(String::from_utf8_lossy(&out.stdout).into_owned())
+}
+
+/// Extract a tar archive that passed check_archive into `dest` with the
+/// host's tar.
+fn extract_archive(archive: &Path, dest: &Path) -> Result<()> {
+    fs::create_dir_all(dest).with_context(|| format!("create dir {}", dest.display()))?;
+    let out = Command::new("tar")
+        .arg("-xf").arg(archive)
+        .arg("-C").arg(dest)
+        .arg("--no-same-owner")
+        .stdin(Stdio::null())
+        .output()
+        .context("run tar")?;
+    if !out.status.success() {
+        anyhow::bail!("tar could not extract it: {}", String::from_utf8_lossy(&out.stderr).trim());
+    }
+    Ok(())
+}
+
+/// Fail if the archive's file contents exceed `max` bytes. Counts what
+/// `tar -xO` actually streams rather than parsing `tar -tv` columns, whose
+/// layout differs between tar implementations; stops reading at the limit.
+fn check_archive_bytes(archive: &Path, max: u64) -> Result<()> {
+    use std::io::Read;
+    let mut child = Command::new("tar")
+        .arg("-xOf").arg(archive)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .context("run tar")?;
+    let mut stdout = child.stdout.take().expect("piped stdout");
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut bytes = 0u64;
+    loop {
+        let n = match stdout.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e).context("read tar output"),
+        };
+        bytes += n as u64;
+        if bytes > max {
+            let _ = child.kill();
+            let _ = child.wait();
+            anyhow::bail!("unpacks to more than {} bytes", max);
+        }
+    }
+    let status = child.wait().context("wait for tar")?;
+    if !status.success() {
+        anyhow::bail!("tar could not read its contents (exit {})", status.code().unwrap_or(-1));
+    }
+    Ok(())
+}
+
+/// Render a Command as a copy-pasteable shell command line.
+pub fn command_line(cmd: &Command) -> String {
+    std::iter::once(cmd.get_program())
+        .chain(cmd.get_args())
+        .map(|a| shell_escape(&a.to_string_lossy()))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
 /// Escape a string for shell use.
 pub fn shell_escape(s: &str) -> String {
     if s.is_empty() { return "''".to_string(); }
@@ -239,3 +523,93 @@ pub fn shell_escape(s: &str) -> String {
         format!("'{}'", s.replace('\'', "'\\''"))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unpack_archive_checks_listing() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("src");
+        fs::create_dir_all(src.join("repo/.git")).unwrap();
+        fs::write(src.join("repo/.git/HEAD"), "ref: refs/heads/main\n").unwrap();
+        fs::write(src.join("repo/README"), "hello\n").unwrap();
+
+        let tar = |name: &str, extra: &[&str]| {
+            let path = dir.path().join(name);
+            let status = Command::new("tar")
+                .arg("-czf").arg(&path).arg("-C").arg(&src).args(extra).arg("repo")
+                .stderr(Stdio::null())
+                .status().unwrap();
+            assert!(status.success());
+            path
+        };
+
+        let dest = dir.path().join("cell");
+        let checked = Mutex::new(HashSet::new());
+        let ok = tar("ok.tar.gz", &[]);
+        unpack_archive(&ok, &dest, "sub", &checked).unwrap();
+        assert_eq!(fs::read_to_string(dest.join("sub/repo/.git/HEAD")).unwrap(), "ref: refs/heads/main\n");
+        assert!(checked.lock().unwrap().contains(&ok));
+
+        let escaping = tar("bad.tar.gz", &["-P", "--transform", "s,^,../,"]);
+        let err = unpack_archive(&escaping, &dest, ".", &checked).unwrap_err().to_string();
+        assert!(err.contains("escapes the workspace"), "{}", err);
+        assert!(!dir.path().join("repo").exists());
+
+        assert!(unpack_archive(&dir.path().join("missing.tar"), &dest, ".", &checked).is_err());
+
+        // Link entries pointing out of the workspace
+        std::os::unix::fs::symlink("/etc", src.join("repo/etc")).unwrap();
+        let err = unpack_archive(&tar("link.tar.gz", &[]), &dest, ".", &checked).unwrap_err().to_string();
+        assert!(err.contains("link \"repo/etc\" -> \"/etc\" escapes the workspace"), "{}", err);
+        fs::remove_file(src.join("repo/etc")).unwrap();
+        // GNU tar strips `../` from hardlink targets itself; other tars may not
+        assert_eq!(link_target("hrw-r--r-- u/g 0 2026-10-16 11:18 repo/a link to ../b", "repo/a"), Some("../b"));
+        assert_eq!(link_target("-rw-r--r-- u/g 2 2026-10-16 11:18 repo/a -> b", "repo/a -> b"), None);
+
+        // `into` leading through a link made by an earlier setup command
+        std::os::unix::fs::symlink(dir.path(), dest.join("out")).unwrap();
+        let err = unpack_archive(&ok, &dest, "out/x", &checked).unwrap_err().to_string();
+        assert!(err.contains("passes through the link \"out\""), "{}", err);
+        assert!(!dir.path().join("x").exists());
+    }
+
+    #[test]
+    fn test_archive_bytes_counted_from_contents() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("src");
+        fs::create_dir_all(&src).unwrap();
+        fs::write(src.join("a"), vec![b'x'; 3000]).unwrap();
+        fs::write(src.join("b"), vec![b'y'; 3000]).unwrap();
+        let path = dir.path().join("t.tar.gz");
+        assert!(Command::new("tar").arg("-czf").arg(&path).arg("-C").arg(&src).args(["a", "b"])
+            .status().unwrap().success());
+
+        check_archive_bytes(&path, 6000).unwrap();
+        let err = check_archive_bytes(&path, 5999).unwrap_err().to_string();
+        assert!(err.contains("more than 5999 bytes"), "{}", err);
+    }
+
+    #[test]
+    fn test_batch_timeout_saturates() {
+        let sandbox = Sandbox::at(PathBuf::from("/usr/bin/bwrap"));
+        assert_eq!(sandbox.batch_timeout_ms(3), 8000);
+        let sandbox = sandbox.with_cell_timeout_ms(Some(u64::MAX / 2)).unwrap();
+        assert_eq!(sandbox.batch_timeout_ms(3), u64::MAX);
+    }
+
+    #[test]
+    fn test_cell_command_line_is_standalone() {
+        let sandbox = Sandbox::at(PathBuf::from("/usr/bin/bwrap"));
+        let env = HashMap::from([("TZ".to_string(), "UTC".to_string())]);
+        let line = sandbox.cell_command_line(
+            Path::new("/tmp/bgrid_batch_x/c3"), &env, "sort", "-r input.txt", "printf 'b\\na' | ");
+        assert!(line.starts_with("printf 'b\\na' | /usr/bin/bwrap --unshare-net"), "{}", line);
+        assert!(line.contains(" --bind /tmp/bgrid_batch_x/c3 /workspace --chdir /workspace "), "{}", line);
+        assert!(line.contains(" --setenv TZ UTC "), "{}", line);
+        assert!(line.ends_with(" -- timeout 2 sort -r input.txt"), "{}", line);
+        assert!(!line.contains("/batch"), "{}", line);
+    }
+}